@@ -0,0 +1,430 @@
+use crate::operator::{BinaryOperator, UnaryOperator};
+use crate::parser::Expr;
+use crate::source_code::SourceSpan;
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+  Natural(i64),
+  Real(f64),
+  Char(char),
+  Boolean(bool),
+}
+
+impl Value {
+  fn type_name(&self) -> &'static str {
+    match self {
+      Value::Natural(_) => "natural",
+      Value::Real(_) => "real",
+      Value::Char(_) => "char",
+      Value::Boolean(_) => "boolean",
+    }
+  }
+}
+
+impl fmt::Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Value::Natural(value) => write!(f, "{}", value),
+      Value::Real(value) if value.fract() == 0.0 => write!(f, "{:.1}", value),
+      Value::Real(value) => write!(f, "{}", value),
+      Value::Char(character) => write!(f, "{}", character),
+      Value::Boolean(value) => write!(f, "{}", value),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EvalError {
+  DivisionByZero {
+    source_span: SourceSpan,
+    message: String,
+  },
+  Overflow {
+    source_span: SourceSpan,
+    message: String,
+  },
+  TypeMismatch {
+    source_span: SourceSpan,
+    message: String,
+  },
+  NotConstant {
+    source_span: SourceSpan,
+    message: String,
+  },
+}
+
+impl EvalError {
+  pub fn source_span(&self) -> SourceSpan {
+    self.parts().0
+  }
+
+  pub fn message(&self) -> &str {
+    self.parts().1
+  }
+
+  fn parts(&self) -> (SourceSpan, &str) {
+    match self {
+      EvalError::DivisionByZero {
+        source_span,
+        message,
+      }
+      | EvalError::Overflow {
+        source_span,
+        message,
+      }
+      | EvalError::TypeMismatch {
+        source_span,
+        message,
+      }
+      | EvalError::NotConstant {
+        source_span,
+        message,
+      } => (*source_span, message),
+    }
+  }
+}
+
+impl fmt::Display for EvalError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let source_span = self.source_span();
+
+    write!(
+      f,
+      "line {}, column {}: {}",
+      source_span.line,
+      source_span.column,
+      self.message()
+    )
+  }
+}
+
+impl std::error::Error for EvalError {}
+
+pub fn eval(expr: &Expr) -> Result<Value, EvalError> {
+  match expr {
+    Expr::Integer(value, _) => Ok(Value::Natural(*value)),
+    Expr::Real(value, _) => Ok(Value::Real(*value)),
+    Expr::Char(character, _) => Ok(Value::Char(*character)),
+    Expr::Boolean(value, _) => Ok(Value::Boolean(*value)),
+    Expr::String(_, source_span) => Err(EvalError::NotConstant {
+      source_span: *source_span,
+      message: "strings can't be evaluated yet".to_owned(),
+    }),
+    Expr::Identifier(identifier, source_span) => Err(EvalError::NotConstant {
+      source_span: *source_span,
+      message: format!("{} is not a constant", identifier),
+    }),
+    Expr::Unary {
+      operator,
+      operator_source_span,
+      operand,
+    } => eval_unary(*operator, *operator_source_span, eval(operand)?),
+    Expr::Binary {
+      operator: BinaryOperator::And,
+      left,
+      right,
+      ..
+    } => match eval_boolean(left)? {
+      false => Ok(Value::Boolean(false)),
+      true => eval_boolean(right).map(Value::Boolean),
+    },
+    Expr::Binary {
+      operator: BinaryOperator::Or,
+      left,
+      right,
+      ..
+    } => match eval_boolean(left)? {
+      true => Ok(Value::Boolean(true)),
+      false => eval_boolean(right).map(Value::Boolean),
+    },
+    Expr::Binary {
+      operator,
+      operator_source_span,
+      left,
+      right,
+    } => eval_binary(*operator, *operator_source_span, eval(left)?, eval(right)?),
+  }
+}
+
+fn eval_boolean(expr: &Expr) -> Result<bool, EvalError> {
+  match eval(expr)? {
+    Value::Boolean(value) => Ok(value),
+    value => Err(EvalError::TypeMismatch {
+      source_span: expr.source_span(),
+      message: format!("expected a boolean, found a {}", value.type_name()),
+    }),
+  }
+}
+
+fn eval_unary(
+  operator: UnaryOperator,
+  operator_source_span: SourceSpan,
+  operand: Value,
+) -> Result<Value, EvalError> {
+  match (operator, operand) {
+    (UnaryOperator::Plus, operand @ Value::Natural(_))
+    | (UnaryOperator::Plus, operand @ Value::Real(_)) => Ok(operand),
+    (UnaryOperator::Negate, Value::Natural(value)) => value
+      .checked_neg()
+      .map(Value::Natural)
+      .ok_or_else(|| overflow(operator_source_span)),
+    (UnaryOperator::Negate, Value::Real(value)) => Ok(Value::Real(-value)),
+    (UnaryOperator::Not, Value::Boolean(value)) => Ok(Value::Boolean(!value)),
+    (operator, operand) => Err(EvalError::TypeMismatch {
+      source_span: operator_source_span,
+      message: format!("{} can't be applied to a {}", operator, operand.type_name()),
+    }),
+  }
+}
+
+fn overflow(source_span: SourceSpan) -> EvalError {
+  EvalError::Overflow {
+    source_span,
+    message: format!(
+      "the result doesn't fit in a natural, which is at most {}",
+      i64::MAX
+    ),
+  }
+}
+
+fn is_zero(value: Value) -> bool {
+  match value {
+    Value::Natural(value) => value == 0,
+    Value::Real(value) => value == 0.0,
+    _ => false,
+  }
+}
+
+// Naturals are promoted to reals whenever they meet one.
+fn as_reals(left: Value, right: Value) -> Option<(f64, f64)> {
+  match (left, right) {
+    (Value::Natural(left), Value::Real(right)) => Some((left as f64, right)),
+    (Value::Real(left), Value::Natural(right)) => Some((left, right as f64)),
+    (Value::Real(left), Value::Real(right)) => Some((left, right)),
+    _ => None,
+  }
+}
+
+fn eval_binary(
+  operator: BinaryOperator,
+  operator_source_span: SourceSpan,
+  left: Value,
+  right: Value,
+) -> Result<Value, EvalError> {
+  use BinaryOperator::*;
+
+  let is_division = matches!(operator, Divide | IntegerDivide | Remainder | Modulo);
+
+  if is_division && is_zero(right) {
+    return Err(EvalError::DivisionByZero {
+      source_span: operator_source_span,
+      message: format!("can't apply {} with a divisor of zero", operator),
+    });
+  }
+
+  let checked = |value: Option<i64>| {
+    value
+      .map(Value::Natural)
+      .ok_or_else(|| overflow(operator_source_span))
+  };
+
+  let value = match (operator, left, right) {
+    (Add, Value::Natural(left), Value::Natural(right)) => checked(left.checked_add(right))?,
+    (Subtract, Value::Natural(left), Value::Natural(right)) => checked(left.checked_sub(right))?,
+    (Multiply, Value::Natural(left), Value::Natural(right)) => checked(left.checked_mul(right))?,
+    (Divide, Value::Natural(left), Value::Natural(right)) => {
+      Value::Real(left as f64 / right as f64)
+    }
+    (IntegerDivide, Value::Natural(left), Value::Natural(right)) => {
+      checked(left.checked_div_euclid(right))?
+    }
+    (Remainder, Value::Natural(left), Value::Natural(right)) => checked(left.checked_rem(right))?,
+    (Modulo, Value::Natural(left), Value::Natural(right)) => {
+      checked(left.checked_rem_euclid(right))?
+    }
+    (Power, Value::Natural(left), Value::Natural(right)) if right >= 0 => {
+      let exponent = u32::try_from(right).map_err(|_| overflow(operator_source_span))?;
+
+      checked(left.checked_pow(exponent))?
+    }
+    (Power, Value::Natural(left), Value::Natural(right)) => {
+      Value::Real((left as f64).powf(right as f64))
+    }
+    (Equal, left, right) if left.type_name() == right.type_name() => Value::Boolean(left == right),
+    (NotEqual, left, right) if left.type_name() == right.type_name() => {
+      Value::Boolean(left != right)
+    }
+    (LessThan, Value::Natural(left), Value::Natural(right)) => Value::Boolean(left < right),
+    (GreaterThan, Value::Natural(left), Value::Natural(right)) => Value::Boolean(left > right),
+    (LessThanOrEqual, Value::Natural(left), Value::Natural(right)) => Value::Boolean(left <= right),
+    (GreaterThanOrEqual, Value::Natural(left), Value::Natural(right)) => {
+      Value::Boolean(left >= right)
+    }
+    (LessThan, Value::Char(left), Value::Char(right)) => Value::Boolean(left < right),
+    (GreaterThan, Value::Char(left), Value::Char(right)) => Value::Boolean(left > right),
+    (LessThanOrEqual, Value::Char(left), Value::Char(right)) => Value::Boolean(left <= right),
+    (GreaterThanOrEqual, Value::Char(left), Value::Char(right)) => Value::Boolean(left >= right),
+    (operator, left, right) => match as_reals(left, right) {
+      Some((left, right)) => match operator {
+        Add => Value::Real(left + right),
+        Subtract => Value::Real(left - right),
+        Multiply => Value::Real(left * right),
+        Divide => Value::Real(left / right),
+        IntegerDivide => Value::Real(left.div_euclid(right)),
+        Remainder => Value::Real(left % right),
+        Modulo => Value::Real(left.rem_euclid(right)),
+        Power => Value::Real(left.powf(right)),
+        Equal => Value::Boolean(left == right),
+        NotEqual => Value::Boolean(left != right),
+        LessThan => Value::Boolean(left < right),
+        GreaterThan => Value::Boolean(left > right),
+        LessThanOrEqual => Value::Boolean(left <= right),
+        GreaterThanOrEqual => Value::Boolean(left >= right),
+        And | Or => unreachable!("and and or are evaluated lazily"),
+      },
+      None => {
+        return Err(EvalError::TypeMismatch {
+          source_span: operator_source_span,
+          message: format!(
+            "{} can't be applied to a {} and a {}",
+            operator,
+            left.type_name(),
+            right.type_name()
+          ),
+        })
+      }
+    },
+  };
+
+  Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lex_luthor::tokenize;
+  use crate::parser::parse_expression;
+
+  fn eval_source(input: &str) -> Result<Value, EvalError> {
+    eval(&parse_expression(tokenize(input).unwrap()).unwrap())
+  }
+
+  #[test]
+  fn evaluates_arithmetic() {
+    let test_cases = vec![
+      ("2 + 3 * 4", Value::Natural(14)),
+      ("(2 + 3) * 4", Value::Natural(20)),
+      ("2 ** 3 ** 2", Value::Natural(512)),
+      ("-7 // 2", Value::Natural(-4)),
+      ("-7 % 3", Value::Natural(-1)),
+      ("-7 %% 3", Value::Natural(2)),
+      ("7 / 2", Value::Real(3.5)),
+      ("1 + 0.5", Value::Real(1.5)),
+      ("2 ** -1", Value::Real(0.5)),
+      ("-(1.5)", Value::Real(-1.5)),
+      ("+2 - +1.5", Value::Real(0.5)),
+    ];
+
+    for (input, expected) in test_cases {
+      assert_eq!(Ok(expected), eval_source(input), "{}", input);
+    }
+  }
+
+  #[test]
+  fn evaluates_comparisons_and_logic() {
+    let test_cases = vec![
+      ("1 < 2", true),
+      ("2 <= 1.5", false),
+      ("'a' < 'b'", true),
+      ("'a' == 'a'", true),
+      ("1 == 1.0", true),
+      ("true != false", true),
+      ("1 < 2 & 2 < 1", false),
+      ("not (1 > 2) | false", true),
+      ("false & 1 / 0 == 1", false),
+      ("true || 1 / 0 == 1", true),
+    ];
+
+    for (input, expected) in test_cases {
+      assert_eq!(
+        Ok(Value::Boolean(expected)),
+        eval_source(input),
+        "{}",
+        input
+      );
+    }
+  }
+
+  #[test]
+  fn errors_on_division_by_zero() {
+    assert_eq!(
+      Err(EvalError::DivisionByZero {
+        source_span: SourceSpan::new(1, 3, 1, 3),
+        message: "can't apply / with a divisor of zero".to_owned(),
+      }),
+      eval_source("1 / 0")
+    );
+    assert_eq!(
+      Err(EvalError::DivisionByZero {
+        source_span: SourceSpan::new(1, 9, 1, 10),
+        message: "can't apply %% with a divisor of zero".to_owned(),
+      }),
+      eval_source("1.5 + 2 %% 0.0")
+    );
+  }
+
+  #[test]
+  fn errors_on_invalid_operands() {
+    let test_cases = vec![
+      (
+        "1 + true",
+        EvalError::TypeMismatch {
+          source_span: SourceSpan::new(1, 3, 1, 3),
+          message: "+ can't be applied to a natural and a boolean".to_owned(),
+        },
+      ),
+      (
+        "-'a'",
+        EvalError::TypeMismatch {
+          source_span: SourceSpan::new(1, 1, 1, 1),
+          message: "- can't be applied to a char".to_owned(),
+        },
+      ),
+      (
+        "+true",
+        EvalError::TypeMismatch {
+          source_span: SourceSpan::new(1, 1, 1, 1),
+          message: "+ can't be applied to a boolean".to_owned(),
+        },
+      ),
+      (
+        "1 & true",
+        EvalError::TypeMismatch {
+          source_span: SourceSpan::new(1, 1, 1, 1),
+          message: "expected a boolean, found a natural".to_owned(),
+        },
+      ),
+      (
+        "9223372036854775807 + 1",
+        EvalError::Overflow {
+          source_span: SourceSpan::new(1, 21, 1, 21),
+          message: "the result doesn't fit in a natural, which is at most 9223372036854775807"
+            .to_owned(),
+        },
+      ),
+      (
+        "x + 1",
+        EvalError::NotConstant {
+          source_span: SourceSpan::new(1, 1, 1, 1),
+          message: "x is not a constant".to_owned(),
+        },
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      assert_eq!(Err(expected), eval_source(input), "{}", input);
+    }
+  }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(usize);
+
+// Stores each distinct name once. Every occurrence of a name maps to the same Symbol
+// and the same allocation.
+#[derive(Debug, Default)]
+pub struct Interner {
+  symbols: HashMap<Rc<str>, Symbol>,
+  names: Vec<Rc<str>>,
+}
+
+impl Interner {
+  pub fn new() -> Interner {
+    Interner::default()
+  }
+
+  pub fn intern(&mut self, name: &str) -> Symbol {
+    if let Some(symbol) = self.symbols.get(name) {
+      return *symbol;
+    }
+
+    let symbol = Symbol(self.names.len());
+    let name: Rc<str> = Rc::from(name);
+
+    self.names.push(Rc::clone(&name));
+    self.symbols.insert(name, symbol);
+
+    symbol
+  }
+
+  pub fn get(&self, name: &str) -> Option<Symbol> {
+    self.symbols.get(name).copied()
+  }
+
+  pub fn resolve(&self, symbol: Symbol) -> &Rc<str> {
+    &self.names[symbol.0]
+  }
+
+  pub fn len(&self) -> usize {
+    self.names.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.names.is_empty()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn interns_each_name_once() {
+    let mut interner = Interner::new();
+
+    let x = interner.intern("x");
+    let y = interner.intern("y");
+
+    assert_eq!(x, interner.intern("x"));
+    assert_ne!(x, y);
+    assert_eq!(2, interner.len());
+    assert_eq!("y", &**interner.resolve(y));
+    assert_eq!(Some(y), interner.get("y"));
+    assert_eq!(None, interner.get("z"));
+  }
+}
@@ -1,55 +1,588 @@
+use crate::interner::{Interner, Symbol};
 use crate::source_code::SourceSpan;
 use crate::token::*;
+use std::borrow::Cow;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::io::{self, Read};
+use std::ops::Range;
+use std::rc::Rc;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LexLuthorError {
   UnexpectedCharacter {
     source_span: SourceSpan,
     message: String,
   },
+  // source_span points at the offending character, identifier_source_span covers the
+  // whole identifier.
   InvalidIdentifier {
     source_span: SourceSpan,
+    identifier_source_span: SourceSpan,
     message: String,
   },
+  InvalidNumber {
+    source_span: SourceSpan,
+    message: String,
+  },
+  UnterminatedComment {
+    source_span: SourceSpan,
+    message: String,
+  },
+  UnterminatedString {
+    source_span: SourceSpan,
+    message: String,
+  },
+  InvalidEscape {
+    source_span: SourceSpan,
+    message: String,
+  },
+  InvalidCharLiteral {
+    source_span: SourceSpan,
+    message: String,
+  },
+  IdentifierTooLong {
+    source_span: SourceSpan,
+    message: String,
+  },
+  TooManyErrors {
+    source_span: SourceSpan,
+    message: String,
+  },
+}
+
+impl LexLuthorError {
+  pub fn source_span(&self) -> SourceSpan {
+    self.parts().0
+  }
+
+  pub fn message(&self) -> &str {
+    self.parts().1
+  }
+
+  // A stable identifier for the kind of error, so editors can link to documentation.
+  // Codes are never reused, new variants get the next one.
+  pub fn code(&self) -> &'static str {
+    match self {
+      LexLuthorError::UnexpectedCharacter { .. } => "E0001",
+      LexLuthorError::InvalidIdentifier { .. } => "E0002",
+      LexLuthorError::InvalidNumber { .. } => "E0003",
+      LexLuthorError::UnterminatedComment { .. } => "E0004",
+      LexLuthorError::UnterminatedString { .. } => "E0005",
+      LexLuthorError::InvalidEscape { .. } => "E0006",
+      LexLuthorError::InvalidCharLiteral { .. } => "E0007",
+      LexLuthorError::IdentifierTooLong { .. } => "E0008",
+      // E0009 was MixedIndentation, which is a warning now.
+      LexLuthorError::TooManyErrors { .. } => "E0010",
+    }
+  }
+
+  fn parts(&self) -> (SourceSpan, &str) {
+    match self {
+      LexLuthorError::UnexpectedCharacter {
+        source_span,
+        message,
+      }
+      | LexLuthorError::InvalidIdentifier {
+        source_span,
+        message,
+        ..
+      }
+      | LexLuthorError::InvalidNumber {
+        source_span,
+        message,
+      }
+      | LexLuthorError::UnterminatedComment {
+        source_span,
+        message,
+      }
+      | LexLuthorError::UnterminatedString {
+        source_span,
+        message,
+      }
+      | LexLuthorError::InvalidEscape {
+        source_span,
+        message,
+      }
+      | LexLuthorError::InvalidCharLiteral {
+        source_span,
+        message,
+      }
+      | LexLuthorError::IdentifierTooLong {
+        source_span,
+        message,
+      }
+      | LexLuthorError::TooManyErrors {
+        source_span,
+        message,
+      } => (*source_span, message),
+    }
+  }
+}
+
+impl fmt::Display for LexLuthorError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let source_span = self.source_span();
+
+    write!(
+      f,
+      "line {}, column {}: {}",
+      source_span.line,
+      source_span.column,
+      self.message()
+    )
+  }
+}
+
+impl std::error::Error for LexLuthorError {}
+
+// Every error lex found as a single value, so callers can use ? on its result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexLuthorErrors(pub Vec<LexLuthorError>);
+
+impl From<Vec<LexLuthorError>> for LexLuthorErrors {
+  fn from(errors: Vec<LexLuthorError>) -> LexLuthorErrors {
+    LexLuthorErrors(errors)
+  }
+}
+
+impl fmt::Display for LexLuthorErrors {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (index, error) in self.0.iter().enumerate() {
+      if index > 0 {
+        writeln!(f)?;
+      }
+
+      write!(f, "{}", error)?;
+    }
+
+    Ok(())
+  }
+}
+
+impl std::error::Error for LexLuthorErrors {}
+
+// Problems worth pointing out that don't stop the source code from being lexed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexLuthorWarning {
+  DeprecatedOperator {
+    source_span: SourceSpan,
+    message: String,
+  },
+  MixedIndentation {
+    source_span: SourceSpan,
+    message: String,
+  },
+}
+
+impl LexLuthorWarning {
+  pub fn source_span(&self) -> SourceSpan {
+    match self {
+      LexLuthorWarning::DeprecatedOperator { source_span, .. }
+      | LexLuthorWarning::MixedIndentation { source_span, .. } => *source_span,
+    }
+  }
+
+  pub fn message(&self) -> &str {
+    match self {
+      LexLuthorWarning::DeprecatedOperator { message, .. }
+      | LexLuthorWarning::MixedIndentation { message, .. } => message,
+    }
+  }
+}
+
+impl fmt::Display for LexLuthorWarning {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let source_span = self.source_span();
+
+    write!(
+      f,
+      "line {}, column {}: {}",
+      source_span.line,
+      source_span.column,
+      self.message()
+    )
+  }
+}
+
+pub type Tokens = Vec<Token>;
+
+// Every token, or every error when there was at least one.
+pub type LexResult = Result<Tokens, Vec<LexLuthorError>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexEvent {
+  Token(Token),
+  Error(LexLuthorError),
 }
 
 #[derive(Debug)]
-pub struct LexLuthor {
-  source_code: String,
+pub struct LexLuthor<'a> {
+  // Borrowed when lexing a &str the caller keeps around, so it isn't copied.
+  source_code: Cow<'a, str>,
+  // Where the source code starts, 1 and 1 unless it is a snippet of a larger document.
+  start_line: usize,
+  start_column: usize,
   line: usize,
   column: usize,
+  // Byte offset just past `character`.
   position: usize,
+  // Byte offset where each line lexed so far starts.
+  line_starts: Vec<usize>,
   character: char,
+  finished: bool,
+  case_insensitive_keywords: bool,
+  preserve_comments: bool,
+  line_comment_prefix: String,
+  significant_newlines: bool,
+  warn_on_mixed_indentation: bool,
+  deprecations: bool,
+  extra_keywords: HashSet<String>,
+  question_token: bool,
+  max_errors: Option<usize>,
+  max_identifier_length: Option<usize>,
+  tab_width: usize,
+  interner: Option<Interner>,
+  lookahead: VecDeque<Result<Token, LexLuthorError>>,
+  warnings: Vec<LexLuthorWarning>,
 }
 
-impl LexLuthor {
-  pub fn new(source_code: String) -> LexLuthor {
+impl<'a> LexLuthor<'a> {
+  pub fn new(source_code: String) -> LexLuthor<'a> {
+    LexLuthor::from_cow(Cow::Owned(source_code), 1, 1)
+  }
+
+  pub fn new_borrowed(source_code: &'a str) -> LexLuthor<'a> {
+    LexLuthor::from_cow(Cow::Borrowed(source_code), 1, 1)
+  }
+
+  // For snippets taken out of a larger document, so spans point into the document
+  // instead of the snippet. Lines after the first one start at column 1.
+  pub fn new_at(source_code: String, start_line: usize, start_column: usize) -> LexLuthor<'a> {
+    LexLuthor::from_cow(
+      Cow::Owned(source_code),
+      start_line.max(1),
+      start_column.max(1),
+    )
+  }
+
+  fn from_cow(source_code: Cow<'a, str>, start_line: usize, start_column: usize) -> LexLuthor<'a> {
     let mut lex_luthor = LexLuthor {
       source_code,
-      line: 1,
-      column: 0,
+      start_line,
+      start_column,
+      line: start_line,
+      column: start_column - 1,
       position: 0,
+      line_starts: vec![0],
       character: '\0',
+      finished: false,
+      case_insensitive_keywords: false,
+      preserve_comments: false,
+      line_comment_prefix: "#".to_owned(),
+      significant_newlines: false,
+      warn_on_mixed_indentation: false,
+      deprecations: false,
+      extra_keywords: HashSet::new(),
+      question_token: false,
+      max_errors: None,
+      max_identifier_length: None,
+      tab_width: 1,
+      interner: None,
+      lookahead: VecDeque::new(),
+      warnings: Vec::new(),
     };
 
+    lex_luthor.skip_byte_order_mark();
     lex_luthor.read_character();
 
     lex_luthor
   }
 
+  // Reads everything up front, failing with InvalidData if it isn't UTF-8.
+  pub fn from_reader<R: Read>(mut reader: R) -> io::Result<LexLuthor<'a>> {
+    let mut source_code = String::new();
+
+    reader.read_to_string(&mut source_code)?;
+
+    Ok(LexLuthor::new(source_code))
+  }
+
+  pub fn with_case_insensitive_keywords(
+    mut self,
+    case_insensitive_keywords: bool,
+  ) -> LexLuthor<'a> {
+    self.case_insensitive_keywords = case_insensitive_keywords;
+
+    // Extra keywords are looked up lowercased from now on, so they are stored that way.
+    if case_insensitive_keywords {
+      self.extra_keywords = self
+        .extra_keywords
+        .iter()
+        .map(|keyword| keyword.to_lowercase())
+        .collect();
+    }
+
+    self
+  }
+
+  pub fn preserving_comments(mut self, preserve_comments: bool) -> LexLuthor<'a> {
+    self.preserve_comments = preserve_comments;
+    self
+  }
+
+  // What starts a line comment, # unless a dialect wants something like // or --. An
+  // empty prefix turns line comments off. Comments are checked for first, so a prefix
+  // that can start an identifier, number, string, block comment or whitespace would
+  // take those over and is rejected.
+  pub fn with_line_comment_prefix(
+    mut self,
+    line_comment_prefix: &str,
+  ) -> Result<LexLuthor<'a>, String> {
+    let mut characters = line_comment_prefix.chars();
+
+    let taken_over = match (characters.next(), characters.next()) {
+      (Some(character), _) if character.is_ascii_whitespace() => Some("whitespace"),
+      (Some(character), _) if character.is_ascii_digit() => Some("numbers"),
+      // A lone . or / would also match the start of .5 or /*.
+      (Some('.'), None) => Some("numbers"),
+      (Some('.'), Some(character)) if character.is_ascii_digit() => Some("numbers"),
+      (Some(character), _) if is_identifier_character(character) => Some("identifiers"),
+      (Some('"'), _) => Some("strings"),
+      (Some('\''), _) => Some("character literals"),
+      (Some('/'), None) | (Some('/'), Some('*')) => Some("block comments"),
+      _ => None,
+    };
+
+    if let Some(taken_over) = taken_over {
+      return Err(format!(
+        "line comment prefix {:?} would start comments where {} start",
+        line_comment_prefix, taken_over
+      ));
+    }
+
+    self.line_comment_prefix = line_comment_prefix.to_owned();
+    Ok(self)
+  }
+
+  // Line breaks become Newline tokens instead of being skipped, for grammars that
+  // end statements at the end of a line.
+  pub fn with_significant_newlines(mut self, significant_newlines: bool) -> LexLuthor<'a> {
+    self.significant_newlines = significant_newlines;
+    self
+  }
+
+  // Records a MixedIndentation warning for every line indented with both tabs and
+  // spaces. Lexing isn't affected, the lines are lexed like any other.
+  pub fn warn_on_mixed_indentation(mut self, warn_on_mixed_indentation: bool) -> LexLuthor<'a> {
+    self.warn_on_mixed_indentation = warn_on_mixed_indentation;
+    self
+  }
+
+  // & and | are on their way out in favor of && and ||. They are still lexed, but
+  // each one is recorded in warnings.
+  pub fn with_deprecations(mut self, deprecations: bool) -> LexLuthor<'a> {
+    self.deprecations = deprecations;
+    self
+  }
+
+  // Reserves more words on top of the built in keywords. They are lexed as
+  // Token::Keyword instead of as identifiers. With case insensitive keywords they
+  // match in any case and are lexed lowercased, like the built in ones.
+  pub fn with_extra_keywords(mut self, keywords: &[&str]) -> LexLuthor<'a> {
+    let case_insensitive_keywords = self.case_insensitive_keywords;
+
+    self.extra_keywords.extend(keywords.iter().map(|keyword| {
+      if case_insensitive_keywords {
+        keyword.to_lowercase()
+      } else {
+        (*keyword).to_owned()
+      }
+    }));
+    self
+  }
+
+  // ? is an unexpected character unless a grammar extension asks for it.
+  pub fn with_question_token(mut self, question_token: bool) -> LexLuthor<'a> {
+    self.question_token = question_token;
+    self
+  }
+
+  // Stops lex after this many errors, so garbage input doesn't produce an error for
+  // every byte. A TooManyErrors error is added at the end when that happens.
+  pub fn with_max_errors(mut self, max_errors: usize) -> LexLuthor<'a> {
+    self.max_errors = Some(max_errors.max(1));
+    self
+  }
+
+  pub fn with_max_identifier_length(mut self, max_identifier_length: usize) -> LexLuthor<'a> {
+    self.max_identifier_length = Some(max_identifier_length);
+    self
+  }
+
+  pub fn with_tab_width(mut self, tab_width: usize) -> LexLuthor<'a> {
+    self.tab_width = tab_width.max(1);
+    self
+  }
+
+  // Interns every identifier that is lexed. Identifiers then come out as
+  // InternedIdentifier tokens that share one Rc<str> per name, and callers can work
+  // with Symbols instead of comparing strings. The interner is kept across resets.
+  pub fn with_interner(mut self) -> LexLuthor<'a> {
+    self.interner = Some(Interner::new());
+    self
+  }
+
+  pub fn interner(&self) -> Option<&Interner> {
+    self.interner.as_ref()
+  }
+
+  // The symbol of an identifier lexed while interning was enabled.
+  pub fn symbol(&self, token: &Token) -> Option<Symbol> {
+    self.interner.as_ref()?.get(token.identifier()?)
+  }
+
+  // Starts over on new source code, keeping the options the lexer was built with.
+  pub fn reset(&mut self, source_code: String) {
+    self.source_code = Cow::Owned(source_code);
+    self.line = self.start_line;
+    self.column = self.start_column - 1;
+    self.position = 0;
+    self.line_starts = vec![0];
+    self.character = '\0';
+    self.finished = false;
+    self.lookahead.clear();
+    self.warnings.clear();
+
+    self.skip_byte_order_mark();
+    self.read_character();
+  }
+
+  // Some editors start UTF-8 files with a byte order mark. It isn't part of the
+  // program, so lexing starts after it and it takes up no column.
+  fn skip_byte_order_mark(&mut self) {
+    if self.source_code.starts_with('\u{FEFF}') {
+      self.position = '\u{FEFF}'.len_utf8();
+      self.line_starts = vec![self.position];
+    }
+  }
+
+  // Warnings for everything lexed so far, in the order it was lexed.
+  pub fn warnings(&self) -> &[LexLuthorWarning] {
+    &self.warnings
+  }
+
+  fn warn_deprecated_operator(&mut self, operator: &str, replacement: &str) {
+    if self.deprecations {
+      self.warnings.push(LexLuthorWarning::DeprecatedOperator {
+        source_span: self.current_source_span(),
+        message: format!("{} is deprecated, use {} instead", operator, replacement),
+      });
+    }
+  }
+
+  // Line and column of the next character to be lexed. Tokens buffered by
+  // peek_token have already been lexed.
+  pub fn line(&self) -> usize {
+    self.line
+  }
+
+  pub fn column(&self) -> usize {
+    self.column
+  }
+
+  // Byte offset of the next character to be lexed, which is also how many bytes
+  // have been consumed so far.
+  pub fn position(&self) -> usize {
+    self.character_position()
+  }
+
+  // The byte offset of the character at a line and column, for tooling that addresses
+  // source code by bytes. Only lines the lexer has reached are known.
+  pub fn byte_offset(&self, line: usize, column: usize) -> Option<usize> {
+    let start = *self.line_starts.get(line.checked_sub(self.start_line)?)?;
+    let mut current_column = if line == self.start_line {
+      self.start_column
+    } else {
+      1
+    };
+
+    for (index, character) in self.source_code[start..].char_indices() {
+      if current_column >= column {
+        return Some(start + index);
+      }
+
+      // The \r of a \r\n is still on this line, the \n after it takes the next column.
+      let is_line_break = character == '\n'
+        || (character == '\r' && !self.source_code[start + index + 1..].starts_with('\n'));
+
+      if is_line_break {
+        return None;
+      }
+
+      current_column = self.column_after(character, current_column);
+    }
+
+    if current_column >= column {
+      Some(self.source_code.len())
+    } else {
+      None
+    }
+  }
+
+  // The bytes a span covers, including the whole of its last character.
+  pub fn byte_range(&self, source_span: &SourceSpan) -> Option<Range<usize>> {
+    let start = self.byte_offset(source_span.line, source_span.column)?;
+    let end = self.byte_offset(source_span.end_line, source_span.end_column)?;
+    let last_character_length = self.source_code[end..]
+      .chars()
+      .next()
+      .map_or(0, char::len_utf8);
+
+    Some(start..end + last_character_length)
+  }
+
   fn current_source_span(&self) -> SourceSpan {
     SourceSpan {
       line: self.line,
       column: self.column,
+      end_line: self.line,
+      end_column: self.column,
+    }
+  }
+
+  fn source_span_from(&self, start: &SourceSpan) -> SourceSpan {
+    SourceSpan {
+      line: start.line,
+      column: start.column,
+      end_line: self.line,
+      end_column: self.column,
     }
   }
 
+  // Both sides are byte counts, so multi-byte characters are accounted for. This is
+  // the only way to tell the end of input apart from a NUL in the source code,
+  // since `character` is '\0' in both cases.
   fn has_characters_to_lex(&self) -> bool {
     self.position <= self.source_code.len()
   }
 
+  // Byte offset of `character`. Saturates so it can't underflow before the first
+  // character is read.
+  fn character_position(&self) -> usize {
+    self.position.saturating_sub(self.character.len_utf8())
+  }
+
   fn peek(&self) -> Option<char> {
-    self.source_code.chars().nth(self.position)
+    self.peek_nth(0)
+  }
+
+  fn peek_nth(&self, n: usize) -> Option<char> {
+    self
+      .source_code
+      .get(self.position..)
+      .and_then(|rest| rest.chars().nth(n))
   }
 
   fn next_character_is(&self, expected_character: char) -> bool {
@@ -59,540 +592,4432 @@ impl LexLuthor {
     }
   }
 
+  // `line` and `column` are always the 1-based position of `character`, which
+  // is why they are advanced based on the character being left behind. Once
+  // the input runs out `character` becomes '\0' and sits just past the last
+  // character of the source code, where it stays no matter how often we read.
   fn read_character(&mut self) {
-    match self.source_code.chars().nth(self.position) {
-      None => self.character = '\0',
-      Some(character) => {
-        self.character = character;
+    let next_character = self.peek().unwrap_or('\0');
+
+    if self.position <= self.source_code.len() {
+      // \r\n is a single line break, a lone \r is one as well. Leaving the last line
+      // break in the source code counts too, so Eof after one starts a new line.
+      let is_line_break =
+        self.character == '\n' || (self.character == '\r' && next_character != '\n');
 
-        self.column += 1;
+      if is_line_break {
+        self.line += 1;
+        self.column = 1;
 
-        if self.character == '\n' {
-          self.line += 1;
-          self.column = 0;
+        // Lines seen before a lex_token_at are already known.
+        if self.line - self.start_line == self.line_starts.len() {
+          self.line_starts.push(self.position);
         }
+      } else {
+        self.column = self.column_after(self.character, self.column);
       }
     }
 
-    self.position += 1;
+    self.character = next_character;
+
+    self.position += self.character.len_utf8();
+  }
+
+  // Tabs move to the next tab stop, every other character takes up one column.
+  fn column_after(&self, character: char, column: usize) -> usize {
+    if character == '\t' {
+      (column.saturating_sub(1) / self.tab_width + 1) * self.tab_width + 1
+    } else {
+      column + 1
+    }
   }
 
-  fn read_identifier_or_keyword(&mut self) -> Result<String, LexLuthorError> {
-    let start = self.position - 1;
+  // The bytes the identifier or keyword covers. Nothing is allocated for it here, so
+  // the caller decides whether it becomes a String, a shared Rc<str> or a keyword.
+  fn read_identifier_or_keyword(&mut self) -> Result<(Range<usize>, SourceSpan), LexLuthorError> {
+    let start = self.character_position();
+    let start_source_span = self.current_source_span();
 
-    while self.character.is_digit(10) || self.character.is_alphabetic() || self.character == '_' {
+    while matches!(self.peek(), Some(character) if is_identifier_character(character)) {
       self.read_character();
     }
 
-    let identifier_or_keyword: String = self
-      .source_code
-      .chars()
-      .skip(start)
-      .take(self.position - start)
-      .collect();
+    let source_span = self.source_span_from(&start_source_span);
+    let identifier_or_keyword = &self.source_code[start..self.position];
+
+    if let Some(max_identifier_length) = self.max_identifier_length {
+      if identifier_or_keyword.chars().count() > max_identifier_length {
+        let error = LexLuthorError::IdentifierTooLong {
+          source_span: start_source_span,
+          message: format!(
+            "{} is not a valid identifier, identifiers can be at most {} characters long",
+            identifier_or_keyword, max_identifier_length
+          ),
+        };
+
+        self.read_character();
+
+        return Err(error);
+      }
+    }
 
-    if identifier_or_keyword.len() == 1 {
-      return Ok(identifier_or_keyword);
+    // Digits and underscores must be followed by a letter, so identifiers can't end
+    // with either. A lone _ is the one exception, it names a value nobody reads.
+    if identifier_or_keyword == "_" {
+      return Ok((start..self.position, source_span));
     }
 
-    for (index, character) in identifier_or_keyword.chars().enumerate() {
-      if (character.is_digit(10) || character == '_')
-        && !matches!(identifier_or_keyword.chars().nth(index + 1), Some(character) if character.is_alphabetic())
+    let mut characters = identifier_or_keyword.chars().enumerate().peekable();
+
+    while let Some((index, character)) = characters.next() {
+      let next_character = characters.peek().map(|(_, character)| *character);
+
+      if (character.is_ascii_digit() || character == '_')
+        && !matches!(next_character, Some(character) if is_letter(character))
       {
-        return Err(LexLuthorError::InvalidIdentifier {
+        // Point at the character where a letter was expected, or at the offending
+        // character itself when it ends the identifier.
+        let offending_index = if next_character.is_some() {
+          index + 1
+        } else {
+          index
+        };
+        let column = start_source_span.column + offending_index;
+
+        let error = LexLuthorError::InvalidIdentifier {
           message: format!(
             "{} is not a valid identifier, {} must be followed by a letter",
             identifier_or_keyword, character
           ),
-          source_span: self.current_source_span(),
-        });
+          source_span: SourceSpan::new(
+            start_source_span.line,
+            column,
+            start_source_span.line,
+            column,
+          ),
+          identifier_source_span: source_span,
+        };
+
+        self.read_character();
+
+        return Err(error);
       }
     }
 
-    Ok(identifier_or_keyword)
+    Ok((start..self.position, source_span))
   }
 
-  fn skip_whitespace(&mut self) {
-    while self.character.is_ascii_whitespace() {
-      self.read_character();
+  // With an interner every occurrence of a name shares one allocation, otherwise each
+  // identifier gets its own String.
+  fn identifier_token(&mut self, range: Range<usize>, source_span: SourceSpan) -> Token {
+    let identifier = &self.source_code[range];
+
+    match &mut self.interner {
+      Some(interner) => {
+        let symbol = interner.intern(identifier);
+
+        Token::InternedIdentifier(Rc::clone(interner.resolve(symbol)), source_span)
+      }
+      None => Token::Identifier(identifier.to_owned(), source_span),
     }
   }
 
-  fn next_token(&mut self) -> Result<Token, LexLuthorError> {
-    self.skip_whitespace();
+  fn read_identifier_without_a_start(&mut self) -> LexLuthorError {
+    let start = self.character_position();
+    let start_source_span = self.current_source_span();
 
-    let token = match self.character {
-      '{' => Token::LeftBrace(self.current_source_span()),
-      '}' => Token::RightBrace(self.current_source_span()),
-      '[' => Token::LeftBracket(self.current_source_span()),
-      ']' => Token::RightBracket(self.current_source_span()),
-      ',' => Token::Comma(self.current_source_span()),
-      '+' => Token::Plus(self.current_source_span()),
-      '-' => Token::Minus(self.current_source_span()),
-      '/' => Token::Slash(self.current_source_span()),
-      '*' => {
-        if self.next_character_is('*') {
-          self.read_character();
-          Token::StarStar(self.current_source_span())
-        } else {
-          Token::Star(self.current_source_span())
-        }
-      }
-      '%' => {
-        if self.next_character_is('%') {
-          self.read_character();
-          Token::PercentPercent(self.current_source_span())
-        } else {
-          Token::Percent(self.current_source_span())
-        }
-      }
-      '=' => Token::Equal(self.current_source_span()),
-      '!' if self.next_character_is('=') => Token::NotEqual(self.current_source_span()),
-      '<' => {
-        if self.next_character_is('=') {
-          self.read_character();
-          Token::LessThanOrEqual(self.current_source_span())
-        } else {
-          Token::LessThan(self.current_source_span())
-        }
-      }
-      '>' => {
-        if self.next_character_is('=') {
-          self.read_character();
-          Token::GreaterThanOrEqual(self.current_source_span())
-        } else {
-          Token::GreaterThan(self.current_source_span())
-        }
-      }
-      '&' => Token::Ampersand(self.current_source_span()),
-      '|' => Token::Pipe(self.current_source_span()),
-      '!' => Token::Bang(self.current_source_span()),
-      '(' => Token::LeftParen(self.current_source_span()),
-      ')' => Token::RightParen(self.current_source_span()),
-      character if character.is_alphabetic() || character == '_' => {
-        let identifier_or_keyword = self.read_identifier_or_keyword()?;
-        token_from_identifier_or_keyword(identifier_or_keyword, self.current_source_span())
-      }
-      character => {
-        self.read_character();
-        return Err(LexLuthorError::UnexpectedCharacter {
-          source_span: self.current_source_span(),
-          message: format!("unexpected character {}", character),
-        });
-      }
-    };
+    while matches!(self.peek(), Some(character) if is_identifier_character(character)) {
+      self.read_character();
+    }
+
+    let identifier_source_span = self.source_span_from(&start_source_span);
+    let identifier = self.source_code[start..self.position].to_owned();
 
     self.read_character();
 
-    Ok(token)
+    LexLuthorError::InvalidIdentifier {
+      source_span: start_source_span,
+      identifier_source_span,
+      message: format!(
+        "{} is not a valid identifier, identifiers must start with a letter or _",
+        identifier.escape_debug()
+      ),
+    }
   }
 
-  pub fn lex(&mut self) -> Result<Vec<Token>, Vec<LexLuthorError>> {
+  fn extra_keyword(&self, identifier: &str) -> Option<String> {
+    if self.case_insensitive_keywords {
+      self.extra_keywords.get(&identifier.to_lowercase()).cloned()
+    } else {
+      self.extra_keywords.get(identifier).cloned()
+    }
+  }
+
+  fn read_number(&mut self) -> Result<Token, LexLuthorError> {
+    if self.character == '0' {
+      match self.peek() {
+        Some('x') | Some('X') => return self.read_integer_with_radix(16),
+        Some('b') | Some('B') => return self.read_integer_with_radix(2),
+        _ => (),
+      }
+    }
+
+    let start = self.character_position();
+    let start_source_span = self.current_source_span();
+
+    loop {
+      match self.peek() {
+        Some('0'..='9') | Some('_') => (),
+        // 1..5 is a range, not a real number with two dots.
+        Some('.') if self.peek_nth(1) != Some('.') => (),
+        _ => break,
+      }
+
+      self.read_character();
+    }
+
+    // 2x is neither a number nor an identifier, so the letters are read as part of the
+    // number for the error to cover them.
+    let is_followed_by_letters = matches!(self.peek(), Some(character) if is_letter(character));
+
+    while matches!(self.peek(), Some(character) if is_identifier_character(character)) {
+      self.read_character();
+    }
+
+    let number = self.source_code[start..self.position].to_owned();
+    let digits = number.replace('_', "");
+
+    let token = if is_followed_by_letters {
+      Err(format!(
+        "{} is not a valid number, numbers cannot be followed by letters",
+        number
+      ))
+    } else if has_misplaced_digit_separator(&number) {
+      Err(format!(
+        "{} is not a valid number, _ can only be used between digits",
+        number
+      ))
+    } else if number.starts_with('.') {
+      Err(format!(
+        "{} is not a valid real number, real numbers must start with a digit, try 0{}",
+        number, number
+      ))
+    } else if number.matches('.').count() > 1 {
+      Err(format!(
+        "{} is not a valid real number, real numbers can only have one .",
+        number
+      ))
+    } else if number.ends_with('.') {
+      Err(format!(
+        "{} is not a valid real number, . must be followed by a digit",
+        number
+      ))
+    } else if number.contains('.') {
+      digits
+        .parse::<f64>()
+        .map(|value| Token::RealLiteral(value, self.source_span_from(&start_source_span)))
+        .map_err(|_| format!("{} is not a valid real number", number))
+    } else {
+      digits
+        .parse::<i64>()
+        .map(|value| Token::Integer(value, self.source_span_from(&start_source_span)))
+        .map_err(|_| {
+          format!(
+            "{} is not a valid integer, integers must be at most {}",
+            number,
+            i64::MAX
+          )
+        })
+    };
+
+    token.map_err(|message| {
+      let source_span = self.source_span_from(&start_source_span);
+
+      self.read_character();
+
+      LexLuthorError::InvalidNumber {
+        source_span,
+        message,
+      }
+    })
+  }
+
+  // Reads integers written as a 0 followed by a letter naming their radix, like 0xFF.
+  fn read_integer_with_radix(&mut self, radix: u32) -> Result<Token, LexLuthorError> {
+    let start = self.character_position();
+    let start_source_span = self.current_source_span();
+
+    let radix_name = match radix {
+      2 => "binary",
+      16 => "hexadecimal",
+      _ => unreachable!("unsupported radix {}", radix),
+    };
+
+    // Skip the 0 and the radix letter.
+    self.read_character();
+
+    // Letters that aren't digits in this radix are read too so the error covers them.
+    while matches!(self.peek(), Some(character) if character.is_ascii_alphanumeric())
+      || self.next_character_is('_')
+    {
+      self.read_character();
+    }
+
+    let number = self.source_code[start..self.position].to_owned();
+    let prefix = &number[..2];
+    let digits = &number[2..];
+
+    let token = if digits.is_empty() {
+      Err(format!(
+        "{} is not a valid {} integer, {} must be followed by {} digits",
+        number, radix_name, prefix, radix_name
+      ))
+    } else if has_misplaced_digit_separator(digits) {
+      Err(format!(
+        "{} is not a valid number, _ can only be used between digits",
+        number
+      ))
+    } else if let Some((index, character)) = digits
+      .chars()
+      .enumerate()
+      .find(|(_, character)| *character != '_' && !character.is_digit(radix))
+    {
+      let column = start_source_span.column + prefix.len() + index;
+
+      self.read_character();
+
+      return Err(LexLuthorError::InvalidNumber {
+        source_span: SourceSpan::new(
+          start_source_span.line,
+          column,
+          start_source_span.line,
+          column,
+        ),
+        message: format!(
+          "{} is not a valid {} integer, {} is not a {} digit",
+          number, radix_name, character, radix_name
+        ),
+      });
+    } else {
+      i64::from_str_radix(&digits.replace('_', ""), radix)
+        .map(|value| Token::Integer(value, self.source_span_from(&start_source_span)))
+        .map_err(|_| {
+          format!(
+            "{} is not a valid integer, integers must be at most {}",
+            number,
+            i64::MAX
+          )
+        })
+    };
+
+    token.map_err(|message| {
+      let source_span = self.source_span_from(&start_source_span);
+
+      self.read_character();
+
+      LexLuthorError::InvalidNumber {
+        source_span,
+        message,
+      }
+    })
+  }
+
+  fn read_escape_sequence(&mut self) -> Result<char, LexLuthorError> {
+    let source_span = self.current_source_span();
+
+    self.read_character();
+
+    match self.character {
+      'n' => Ok('\n'),
+      'r' => Ok('\r'),
+      't' => Ok('\t'),
+      '\\' => Ok('\\'),
+      '"' => Ok('"'),
+      '\'' => Ok('\''),
+      'u' => self.read_unicode_escape(source_span),
+      character => Err(LexLuthorError::InvalidEscape {
+        source_span,
+        message: format!("unknown escape sequence \\{}", character),
+      }),
+    }
+  }
+
+  // \u{1F600}, one to six hex digits naming a Unicode scalar value. Starts on the u and
+  // stops on the closing }, or before whatever broke the escape so it is still lexed
+  // as part of the literal.
+  fn read_unicode_escape(&mut self, start_source_span: SourceSpan) -> Result<char, LexLuthorError> {
+    let invalid_escape = |source_span, message: String| LexLuthorError::InvalidEscape {
+      source_span,
+      message,
+    };
+
+    if !self.next_character_is('{') {
+      return Err(invalid_escape(
+        self.source_span_from(&start_source_span),
+        "\\u must be followed by a code point in braces, as in \\u{41}".to_owned(),
+      ));
+    }
+
+    self.read_character();
+
+    let mut digits = String::new();
+
+    while let Some(digit) = self.peek().filter(char::is_ascii_hexdigit) {
+      self.read_character();
+      digits.push(digit);
+    }
+
+    if !self.next_character_is('}') {
+      return Err(invalid_escape(
+        self.source_span_from(&start_source_span),
+        format!("unterminated escape sequence \\u{{{}, expected }}", digits),
+      ));
+    }
+
+    self.read_character();
+
+    let source_span = self.source_span_from(&start_source_span);
+
+    if digits.is_empty() || digits.len() > 6 {
+      return Err(invalid_escape(
+        source_span,
+        format!(
+          "\\u{{{}}} is not a valid escape sequence, code points have between 1 and 6 hex digits",
+          digits
+        ),
+      ));
+    }
+
+    u32::from_str_radix(&digits, 16)
+      .ok()
+      .and_then(std::char::from_u32)
+      .ok_or_else(|| {
+        invalid_escape(
+          source_span,
+          format!("\\u{{{}}} is not a valid Unicode code point", digits),
+        )
+      })
+  }
+
+  // Reads until the closing `quote` on the same line, translating escape sequences.
+  // Returns None when the literal is not terminated.
+  fn read_quoted(&mut self, quote: char) -> Option<Result<String, LexLuthorError>> {
+    let mut contents = String::new();
+    let mut invalid_escape = None;
+
+    loop {
+      self.read_character();
+
+      if !self.has_characters_to_lex() || self.character == '\n' || self.character == '\r' {
+        return None;
+      }
+
+      match self.character {
+        character if character == quote => break,
+        '\\' if !matches!(self.peek(), None | Some('\n') | Some('\r')) => {
+          match self.read_escape_sequence() {
+            Ok(character) => contents.push(character),
+            Err(error) => {
+              invalid_escape.get_or_insert(error);
+            }
+          }
+        }
+        character => contents.push(character),
+      }
+    }
+
+    match invalid_escape {
+      None => Some(Ok(contents)),
+      Some(error) => Some(Err(error)),
+    }
+  }
+
+  fn read_string(&mut self) -> Result<Token, LexLuthorError> {
+    let opening_source_span = self.current_source_span();
+
+    match self.read_quoted('"') {
+      None => Err(LexLuthorError::UnterminatedString {
+        source_span: opening_source_span,
+        message: "unterminated string, expected \"".to_owned(),
+      }),
+      Some(Ok(string)) => Ok(Token::String(
+        string,
+        self.source_span_from(&opening_source_span),
+      )),
+      Some(Err(error)) => {
+        self.read_character();
+        Err(error)
+      }
+    }
+  }
+
+  // r"..." takes everything up to the next " as is, line breaks and backslashes
+  // included.
+  fn read_raw_string(&mut self) -> Result<Token, LexLuthorError> {
+    let opening_source_span = self.current_source_span();
+
+    self.read_character();
+
+    let start = self.position;
+
+    loop {
+      self.read_character();
+
+      if !self.has_characters_to_lex() {
+        return Err(LexLuthorError::UnterminatedString {
+          source_span: opening_source_span,
+          message: "unterminated raw string, expected \"".to_owned(),
+        });
+      }
+
+      if self.character == '"' {
+        break;
+      }
+    }
+
+    Ok(Token::String(
+      self.source_code[start..self.character_position()].to_owned(),
+      self.source_span_from(&opening_source_span),
+    ))
+  }
+
+  fn read_char_literal(&mut self) -> Result<Token, LexLuthorError> {
+    let opening_source_span = self.current_source_span();
+
+    let contents = match self.read_quoted('\'') {
+      None => {
+        return Err(LexLuthorError::InvalidCharLiteral {
+          source_span: opening_source_span,
+          message: "unterminated character literal, expected '".to_owned(),
+        })
+      }
+      Some(Ok(contents)) => contents,
+      Some(Err(error)) => {
+        self.read_character();
+        return Err(error);
+      }
+    };
+
+    let mut characters = contents.chars();
+
+    match (characters.next(), characters.next()) {
+      (Some(character), None) => Ok(Token::CharLiteral(
+        character,
+        self.source_span_from(&opening_source_span),
+      )),
+      (None, _) => {
+        self.read_character();
+        Err(LexLuthorError::InvalidCharLiteral {
+          source_span: opening_source_span,
+          message: "empty character literal, character literals must contain exactly one character"
+            .to_owned(),
+        })
+      }
+      _ => {
+        self.read_character();
+        Err(LexLuthorError::InvalidCharLiteral {
+          source_span: opening_source_span,
+          message: format!(
+            "'{}' is not a valid character literal, character literals must contain exactly one character",
+            contents
+          ),
+        })
+      }
+    }
+  }
+
+  fn skip_whitespace(&mut self) {
+    // The span of the indentation on the current line and whether it has tabs and spaces.
+    let mut indentation: Option<(SourceSpan, bool, bool)> = None;
+
+    while self.character.is_ascii_whitespace() && !self.is_significant_newline(self.character) {
+      if self.column == 1 {
+        indentation = Some((self.current_source_span(), false, false));
+      }
+
+      indentation = match (indentation, self.character) {
+        (_, '\n') | (_, '\r') => None,
+        (Some((source_span, tabs, _)), ' ') => {
+          Some((source_span.through(&self.current_source_span()), tabs, true))
+        }
+        (Some((source_span, _, spaces)), '\t') => Some((
+          source_span.through(&self.current_source_span()),
+          true,
+          spaces,
+        )),
+        (indentation, _) => indentation,
+      };
+
+      self.read_character();
+    }
+
+    if let Some((source_span, true, true)) = indentation {
+      if self.warn_on_mixed_indentation
+        && self.has_characters_to_lex()
+        && !self.is_significant_newline(self.character)
+      {
+        self.warnings.push(LexLuthorWarning::MixedIndentation {
+          source_span,
+          message: "indentation mixes tabs and spaces".to_owned(),
+        });
+      }
+    }
+  }
+
+  fn is_significant_newline(&self, character: char) -> bool {
+    self.significant_newlines && (character == '\n' || character == '\r')
+  }
+
+  // Blank lines in between are folded into the same token.
+  fn read_newlines(&mut self) -> Token {
+    let start_source_span = self.current_source_span();
+    let mut end_source_span = start_source_span;
+
+    // Stop on the last line break, skip_whitespace still has to see the indentation of
+    // the line after it.
+    let rest = &self.source_code[self.position..];
+    let whitespace_length = rest
+      .find(|character: char| !character.is_ascii_whitespace())
+      .unwrap_or(rest.len());
+
+    if let Some(last_line_break) = rest[..whitespace_length].rfind(['\n', '\r']) {
+      let end = self.position + last_line_break;
+
+      while self.position <= end {
+        let character = self.peek().unwrap_or('\0');
+
+        self.read_character();
+
+        if self.is_significant_newline(character) {
+          end_source_span = self.current_source_span();
+        }
+      }
+    }
+
+    self.read_character();
+
+    Token::Newline(start_source_span.through(&end_source_span))
+  }
+
+  fn is_at_line_comment(&self) -> bool {
+    !self.line_comment_prefix.is_empty()
+      && self.has_characters_to_lex()
+      && self.source_code[self.character_position()..].starts_with(&self.line_comment_prefix)
+  }
+
+  fn read_line_comment(&mut self) -> Token {
+    let start = self.character_position();
+    let start_source_span = self.current_source_span();
+
+    while matches!(self.peek(), Some(character) if character != '\n' && character != '\r') {
+      self.read_character();
+    }
+
+    let comment = Token::Comment(
+      self.source_code[start..self.position].to_owned(),
+      self.source_span_from(&start_source_span),
+    );
+
+    self.read_character();
+
+    comment
+  }
+
+  fn read_block_comment(&mut self) -> Result<Token, LexLuthorError> {
+    let start = self.character_position();
+    let start_source_span = self.current_source_span();
+
+    // Skip the opening /*
+    self.read_character();
+    self.read_character();
+
+    // Block comments nest, so commenting out code that has a block comment in it works.
+    let mut depth = 1;
+
+    while self.has_characters_to_lex() {
+      if self.character == '/' && self.next_character_is('*') {
+        self.read_character();
+        depth += 1;
+      } else if self.character == '*' && self.next_character_is('/') {
+        self.read_character();
+        depth -= 1;
+
+        if depth > 0 {
+          self.read_character();
+          continue;
+        }
+
+        let comment = Token::Comment(
+          self.source_code[start..self.position].to_owned(),
+          self.source_span_from(&start_source_span),
+        );
+
+        self.read_character();
+
+        return Ok(comment);
+      }
+
+      self.read_character();
+    }
+
+    Err(LexLuthorError::UnterminatedComment {
+      source_span: start_source_span,
+      message: "unterminated block comment, expected */".to_owned(),
+    })
+  }
+
+  fn scan_token(&mut self) -> Result<Token, LexLuthorError> {
+    // Once the end of input is reached every call returns the same Eof without looking
+    // at the source code again.
+    if !self.has_characters_to_lex() {
+      return Ok(Token::Eof(self.current_source_span()));
+    }
+
+    loop {
+      self.skip_whitespace();
+
+      let comment = match self.character {
+        character if self.is_significant_newline(character) => return Ok(self.read_newlines()),
+        _ if self.is_at_line_comment() => self.read_line_comment(),
+        '/' if self.next_character_is('*') => self.read_block_comment()?,
+        _ => break,
+      };
+
+      if self.preserve_comments {
+        return Ok(comment);
+      }
+    }
+
+    if !self.has_characters_to_lex() {
+      return Ok(Token::Eof(self.current_source_span()));
+    }
+
+    let start_source_span = self.current_source_span();
+
+    let token = match self.character {
+      '{' => Token::LeftBrace(self.current_source_span()),
+      '}' => Token::RightBrace(self.current_source_span()),
+      '[' => Token::LeftBracket(self.current_source_span()),
+      ']' => Token::RightBracket(self.current_source_span()),
+      ',' => Token::Comma(self.current_source_span()),
+      ';' => Token::Semicolon(self.current_source_span()),
+      ':' => {
+        if self.next_character_is('=') {
+          self.read_character();
+          Token::ColonEqual(self.source_span_from(&start_source_span))
+        } else {
+          Token::Colon(self.current_source_span())
+        }
+      }
+      '+' => Token::Plus(self.current_source_span()),
+      '-' => Token::Minus(self.current_source_span()),
+      // // is integer division unless it was made the line comment prefix, in which
+      // case it never gets here. /* is handled before we get here and always opens a
+      // comment.
+      '/' => {
+        if self.next_character_is('/') {
+          self.read_character();
+          Token::SlashSlash(self.source_span_from(&start_source_span))
+        } else {
+          Token::Slash(self.current_source_span())
+        }
+      }
+      '*' => {
+        if self.next_character_is('*') {
+          self.read_character();
+          Token::StarStar(self.source_span_from(&start_source_span))
+        } else {
+          Token::Star(self.current_source_span())
+        }
+      }
+      '%' => {
+        if self.next_character_is('%') {
+          self.read_character();
+          Token::PercentPercent(self.source_span_from(&start_source_span))
+        } else {
+          Token::Percent(self.current_source_span())
+        }
+      }
+      '=' => {
+        if self.next_character_is('=') {
+          self.read_character();
+          Token::EqualEqual(self.source_span_from(&start_source_span))
+        } else {
+          Token::Equal(self.current_source_span())
+        }
+      }
+      '!' if self.next_character_is('=') => {
+        self.read_character();
+        Token::NotEqual(self.source_span_from(&start_source_span))
+      }
+      '<' => {
+        if self.next_character_is('=') {
+          self.read_character();
+          Token::LessThanOrEqual(self.source_span_from(&start_source_span))
+        } else if self.next_character_is('<') {
+          self.read_character();
+          Token::LessLess(self.source_span_from(&start_source_span))
+        } else {
+          Token::LessThan(self.current_source_span())
+        }
+      }
+      '>' => {
+        if self.next_character_is('=') {
+          self.read_character();
+          Token::GreaterThanOrEqual(self.source_span_from(&start_source_span))
+        } else if self.next_character_is('>') {
+          self.read_character();
+          Token::GreaterGreater(self.source_span_from(&start_source_span))
+        } else {
+          Token::GreaterThan(self.current_source_span())
+        }
+      }
+      '&' => {
+        if self.next_character_is('&') {
+          self.read_character();
+          Token::AmpersandAmpersand(self.source_span_from(&start_source_span))
+        } else {
+          self.warn_deprecated_operator("&", "&&");
+          Token::Ampersand(self.current_source_span())
+        }
+      }
+      '|' => {
+        if self.next_character_is('|') {
+          self.read_character();
+          Token::PipePipe(self.source_span_from(&start_source_span))
+        } else {
+          self.warn_deprecated_operator("|", "||");
+          Token::Pipe(self.current_source_span())
+        }
+      }
+      '!' => Token::Bang(self.current_source_span()),
+      '^' => Token::Caret(self.current_source_span()),
+      '?' if self.question_token => Token::Question(self.current_source_span()),
+      '(' => Token::LeftParen(self.current_source_span()),
+      ')' => Token::RightParen(self.current_source_span()),
+      '"' => self.read_string()?,
+      'r' if self.next_character_is('"') => self.read_raw_string()?,
+      '\'' => self.read_char_literal()?,
+      '.' if matches!(self.peek(), Some(character) if character.is_ascii_digit()) => {
+        self.read_number()?
+      }
+      '.' => {
+        if self.next_character_is('.') {
+          self.read_character();
+          Token::DotDot(self.source_span_from(&start_source_span))
+        } else {
+          Token::Dot(self.current_source_span())
+        }
+      }
+      character if character.is_ascii_digit() => self.read_number()?,
+      character if is_letter(character) || character == '_' => {
+        let (range, source_span) = self.read_identifier_or_keyword()?;
+        let identifier_or_keyword = &self.source_code[range.clone()];
+
+        let keyword = if self.case_insensitive_keywords {
+          keyword_token(&identifier_or_keyword.to_lowercase(), source_span)
+        } else {
+          keyword_token(identifier_or_keyword, source_span)
+        };
+
+        match keyword {
+          Some(keyword) => keyword,
+          None => match self.extra_keyword(identifier_or_keyword) {
+            Some(keyword) => Token::Keyword(keyword, source_span),
+            None => self.identifier_token(range, source_span),
+          },
+        }
+      }
+      // Combining marks and the like can continue an identifier but not start one.
+      character if is_identifier_character(character) => {
+        return Err(self.read_identifier_without_a_start())
+      }
+      character => {
+        let source_span = self.current_source_span();
+
+        self.read_character();
+
+        return Err(LexLuthorError::UnexpectedCharacter {
+          source_span,
+          message: format!("unexpected character {}", character.escape_debug()),
+        });
+      }
+    };
+
+    self.read_character();
+
+    Ok(token)
+  }
+
+  // Lexes the token covering a byte offset, or the one after it when the offset is in
+  // whitespace, by starting over from the beginning of its line. Lexing carries on
+  // from there afterwards. Offsets inside a block comment that started on an earlier
+  // line are lexed as if the comment wasn't there.
+  pub fn lex_token_at(&mut self, position: usize) -> Result<Token, LexLuthorError> {
+    // Offsets in a byte order mark start at the first character after it.
+    let mut position = position.clamp(self.line_starts[0], self.source_code.len());
+
+    while !self.source_code.is_char_boundary(position) {
+      position -= 1;
+    }
+
+    self.seek_to_line_of(position);
+
+    loop {
+      let token = self.scan_token();
+
+      if matches!(token, Ok(Token::Eof(_))) || self.character_position() > position {
+        return token;
+      }
+    }
+  }
+
+  // Line starts found so far are kept, so byte_offset still knows about lines lexed
+  // before the seek. Only the ones between the last known line and position are added.
+  fn seek_to_line_of(&mut self, position: usize) {
+    let known_lines = self
+      .line_starts
+      .partition_point(|line_start| *line_start <= position);
+
+    if known_lines == self.line_starts.len() {
+      let last_line_start = *self.line_starts.last().unwrap();
+
+      for (index, character) in self.source_code[last_line_start..position].char_indices() {
+        let index = last_line_start + index;
+        let is_line_break = character == '\n'
+          || (character == '\r' && !self.source_code[index + 1..].starts_with('\n'));
+
+        if is_line_break {
+          self.line_starts.push(index + 1);
+        }
+      }
+    }
+
+    let line_index = self
+      .line_starts
+      .partition_point(|line_start| *line_start <= position)
+      - 1;
+    let line = self.start_line + line_index;
+
+    self.position = self.line_starts[line_index];
+    self.line = line;
+    self.column = if line == self.start_line {
+      self.start_column - 1
+    } else {
+      0
+    };
+    self.character = '\0';
+    self.finished = false;
+    self.lookahead.clear();
+
+    self.read_character();
+  }
+
+  pub fn next_token(&mut self) -> Result<Token, LexLuthorError> {
+    match self.lookahead.pop_front() {
+      Some(token) => token,
+      None => self.scan_token(),
+    }
+  }
+
+  pub fn peek_token(&mut self) -> Result<&Token, &LexLuthorError> {
+    self.peek_token_n(0)
+  }
+
+  pub fn peek_token_n(&mut self, n: usize) -> Result<&Token, &LexLuthorError> {
+    while self.lookahead.len() <= n {
+      let token = self.scan_token();
+      self.lookahead.push_back(token);
+    }
+
+    self.lookahead[n].as_ref()
+  }
+
+  pub fn render_diagnostic(&self, error: &LexLuthorError) -> String {
+    self.render_diagnostic_at(error.source_span(), error.message())
+  }
+
+  // The text of a line, counting from 0, without its line break. Splits on the same
+  // line breaks as read_character, so a lone \r ends a line too.
+  fn source_line(&self, index: usize) -> &str {
+    let is_line_break = |character| character == '\n' || character == '\r';
+    let mut rest = &self.source_code[self.line_starts[0]..];
+
+    for _ in 0..index {
+      match rest.find(is_line_break) {
+        None => return "",
+        Some(end) if rest[end..].starts_with("\r\n") => rest = &rest[end + 2..],
+        Some(end) => rest = &rest[end + 1..],
+      }
+    }
+
+    &rest[..rest.find(is_line_break).unwrap_or(rest.len())]
+  }
+
+  // Renders any message, such as a parser error, against this lexer's source.
+  pub fn render_diagnostic_at(&self, source_span: SourceSpan, message: &str) -> String {
+    // Spans of a lexer built with new_at are shifted by where the snippet starts.
+    let line = source_span
+      .line
+      .checked_sub(self.start_line)
+      .map_or("", |index| self.source_line(index));
+
+    // Mirror tabs so the caret lines up however the line is rendered.
+    let mut padding = String::new();
+    let mut column = if source_span.line == self.start_line {
+      self.start_column
+    } else {
+      1
+    };
+
+    for character in line.chars() {
+      if column >= source_span.column {
+        break;
+      }
+
+      padding.push(if character == '\t' { '\t' } else { ' ' });
+      column = self.column_after(character, column);
+    }
+
+    let carets = if source_span.end_line == source_span.line {
+      source_span.end_column.saturating_sub(source_span.column) + 1
+    } else {
+      1
+    };
+
+    let line_number = source_span.line.to_string();
+    let gutter = " ".repeat(line_number.len());
+
+    format!(
+      "error: {}\n{}--> {}:{}\n{} |\n{} | {}\n{} | {}{}",
+      message,
+      gutter,
+      source_span.line,
+      source_span.column,
+      gutter,
+      line_number,
+      line,
+      gutter,
+      padding,
+      "^".repeat(carets)
+    )
+  }
+
+  pub fn lex(&mut self) -> LexResult {
+    let (tokens, errors) = self.lex_with_recovery();
+
+    if !errors.is_empty() {
+      Err(errors)
+    } else {
+      Ok(tokens)
+    }
+  }
+
+  // The number of tokens lex would return, Eof included, without keeping them around.
+  pub fn count_tokens(&mut self) -> Result<usize, Vec<LexLuthorError>> {
+    let mut count = 0;
+    let mut errors = Vec::new();
+
+    self.lex_each(|result| match result {
+      Ok(_) => count += 1,
+      Err(error) => errors.push(error),
+    });
+
+    if !errors.is_empty() {
+      Err(errors)
+    } else {
+      Ok(count)
+    }
+  }
+
+  // Hands every token and error to `callback` as soon as it is lexed, always ending
+  // with Eof. If the lexer gave up with input left, TooManyErrors comes right before
+  // an Eof where it stopped. Nothing is collected along the way.
+  pub fn lex_each<F: FnMut(Result<Token, LexLuthorError>)>(&mut self, mut callback: F) {
+    let mut error_count = 0;
+
+    loop {
+      match self.next_token() {
+        Ok(token @ Token::Eof(_)) => {
+          callback(Ok(token));
+          break;
+        }
+        Ok(token) => callback(Ok(token)),
+        Err(error) => {
+          let source_span = error.source_span();
+
+          callback(Err(error));
+          error_count += 1;
+
+          if let Some(max_errors) = self
+            .max_errors
+            .filter(|max_errors| error_count >= *max_errors)
+          {
+            let stop_source_span = self.current_source_span();
+
+            // Hitting the cap on the last error isn't stopping early, nothing is skipped.
+            match self.next_token() {
+              Ok(eof @ Token::Eof(_)) => callback(Ok(eof)),
+              _ => {
+                callback(Err(LexLuthorError::TooManyErrors {
+                  source_span,
+                  message: format!("too many errors, stopped after {}", max_errors),
+                }));
+                callback(Ok(Token::Eof(stop_source_span)));
+              }
+            }
+
+            break;
+          }
+        }
+      }
+    }
+  }
+
+  // Tokens and errors in the order they appear in the source code.
+  pub fn lex_events(&mut self) -> Vec<LexEvent> {
+    let mut events = Vec::new();
+
+    self.lex_each(|result| {
+      events.push(match result {
+        Ok(token) => LexEvent::Token(token),
+        Err(error) => LexEvent::Error(error),
+      })
+    });
+
+    events
+  }
+
+  // Every identifier up to the first error, for listing the names used in a program.
+  pub fn identifiers(&mut self) -> Vec<(String, SourceSpan)> {
+    let mut identifiers = Vec::new();
+
+    for event in self.lex_events() {
+      match event {
+        LexEvent::Token(token) => {
+          if let Some(identifier) = token.identifier() {
+            identifiers.push((identifier.to_owned(), token.span()))
+          }
+        }
+        LexEvent::Error(_) => break,
+      }
+    }
+
+    identifiers
+  }
+
+  // Like lex, but keeps the tokens that were lexed around any errors.
+  pub fn lex_with_recovery(&mut self) -> (Vec<Token>, Vec<LexLuthorError>) {
     let mut tokens = Vec::new();
     let mut errors = Vec::new();
 
-    while self.has_characters_to_lex() {
-      match self.next_token() {
-        Ok(token) => tokens.push(token),
-        Err(error) => errors.push(error),
-      }
+    for event in self.lex_events() {
+      match event {
+        LexEvent::Token(token) => tokens.push(token),
+        LexEvent::Error(error) => errors.push(error),
+      }
+    }
+
+    (tokens, errors)
+  }
+}
+
+// Letters are Unicode XID_Start characters, so café and naïve are identifiers.
+fn is_letter(character: char) -> bool {
+  unicode_ident::is_xid_start(character)
+}
+
+// XID_Continue covers letters, digits, _ and combining marks.
+fn is_identifier_character(character: char) -> bool {
+  unicode_ident::is_xid_continue(character)
+}
+
+// Underscores separate digits, as in 1_000_000, so they can't start or end a
+// number, come in pairs or touch the decimal point.
+fn has_misplaced_digit_separator(number: &str) -> bool {
+  number.starts_with('_')
+    || number.ends_with('_')
+    || number.contains("__")
+    || number.contains("_.")
+    || number.contains("._")
+}
+
+pub fn tokenize(source: &str) -> LexResult {
+  LexLuthor::new_borrowed(source).lex()
+}
+
+impl<'a> Iterator for LexLuthor<'a> {
+  type Item = Result<Token, LexLuthorError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.finished {
+      return None;
+    }
+
+    let token = self.next_token();
+
+    if let Ok(Token::Eof(_)) = token {
+      self.finished = true;
+    }
+
+    Some(token)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use proptest::prelude::*;
+
+  // Mostly characters the lexer treats specially, so the interesting paths get hit far
+  // more often than with arbitrary strings.
+  fn source_code() -> impl Strategy<Value = String> {
+    prop_oneof![
+      any::<String>(),
+      "[a-zA-Z0-9_xXbBeEur .:;=<>!&|+*/%(){}\\[\\],?#\"'\\\\\t\r\n\u{0}\u{301}\u{feff}é-]*",
+    ]
+  }
+
+  proptest! {
+    #[test]
+    fn lexing_never_panics(
+      source_code in source_code(),
+      tab_width in 0..9usize,
+      offset in 0..64usize,
+    ) {
+      let mut lex_luthor = LexLuthor::new(source_code.clone())
+        .with_tab_width(tab_width)
+        .with_case_insensitive_keywords(true)
+        .with_significant_newlines(true)
+        .warn_on_mixed_indentation(true)
+        .with_deprecations(true)
+        .with_question_token(true)
+        .with_max_identifier_length(8)
+        .with_interner();
+
+      let (tokens, errors) = lex_luthor.lex_with_recovery();
+
+      prop_assert_eq!(Ok(()), assert_well_formed(&tokens));
+
+      for error in &errors {
+        lex_luthor.render_diagnostic(error);
+      }
+
+      for token in &tokens {
+        prop_assert!(lex_luthor.byte_range(&token.span()).is_some());
+      }
+
+      let _ = lex_luthor.lex_token_at(offset);
+
+      let lexed = LexLuthor::new(source_code.clone()).lex();
+
+      prop_assert_eq!(
+        lexed.as_ref().map(Vec::len).map_err(Vec::len),
+        LexLuthor::new(source_code).count_tokens().map_err(|errors| errors.len())
+      );
+    }
+  }
+
+  #[test]
+  fn recognizes_tokens() {
+    let test_cases = vec![
+      (
+        "{",
+        vec![
+          Token::LeftBrace(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "}",
+        vec![
+          Token::RightBrace(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "[",
+        vec![
+          Token::LeftBracket(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "]",
+        vec![
+          Token::RightBracket(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        ",",
+        vec![
+          Token::Comma(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "+",
+        vec![
+          Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "-",
+        vec![
+          Token::Minus(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "/",
+        vec![
+          Token::Slash(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "*",
+        vec![
+          Token::Star(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "**",
+        vec![
+          Token::StarStar(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "%",
+        vec![
+          Token::Percent(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "%%",
+        vec![
+          Token::PercentPercent(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "=",
+        vec![
+          Token::Equal(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "!",
+        vec![
+          Token::Bang(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "<",
+        vec![
+          Token::LessThan(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "<=",
+        vec![
+          Token::LessThanOrEqual(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        ">",
+        vec![
+          Token::GreaterThan(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        ">=",
+        vec![
+          Token::GreaterThanOrEqual(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "&",
+        vec![
+          Token::Ampersand(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "|",
+        vec![
+          Token::Pipe(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "(",
+        vec![
+          Token::LeftParen(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        ")",
+        vec![
+          Token::RightParen(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      ("", vec![Token::Eof(SourceSpan::new(1, 1, 1, 1))]),
+    ];
+
+    for (input, expected_output) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Ok(expected_output), actual);
+    }
+  }
+
+  #[test]
+  fn not_equal_consumes_both_characters() {
+    let test_cases = vec![
+      (
+        "!=",
+        vec![
+          Token::NotEqual(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "!=+",
+        vec![
+          Token::NotEqual(SourceSpan::new(1, 1, 1, 2)),
+          Token::Plus(SourceSpan::new(1, 3, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+    ];
+
+    for (input, expected_output) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Ok(expected_output), actual);
+    }
+  }
+
+  #[test]
+  fn bang_and_equal_combinations() {
+    let test_cases = vec![
+      (
+        "!",
+        vec![
+          Token::Bang(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "!x",
+        vec![
+          Token::Bang(SourceSpan::new(1, 1, 1, 1)),
+          Token::Identifier("x".to_owned(), SourceSpan::new(1, 2, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "!!=",
+        vec![
+          Token::Bang(SourceSpan::new(1, 1, 1, 1)),
+          Token::NotEqual(SourceSpan::new(1, 2, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+      (
+        "!=",
+        vec![
+          Token::NotEqual(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "!==",
+        vec![
+          Token::NotEqual(SourceSpan::new(1, 1, 1, 2)),
+          Token::Equal(SourceSpan::new(1, 3, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+      (
+        "=",
+        vec![
+          Token::Equal(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "=!",
+        vec![
+          Token::Equal(SourceSpan::new(1, 1, 1, 1)),
+          Token::Bang(SourceSpan::new(1, 2, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "==",
+        vec![
+          Token::EqualEqual(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+    ];
+
+    for (input, expected_output) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Ok(expected_output), actual, "{}", input);
+    }
+  }
+
+  #[test]
+  fn logical_operators() {
+    let test_cases = vec![
+      (
+        "&&",
+        vec![
+          Token::AmpersandAmpersand(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "||",
+        vec![
+          Token::PipePipe(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "&",
+        vec![
+          Token::Ampersand(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "|",
+        vec![
+          Token::Pipe(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "&&&",
+        vec![
+          Token::AmpersandAmpersand(SourceSpan::new(1, 1, 1, 2)),
+          Token::Ampersand(SourceSpan::new(1, 3, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+    ];
+
+    for (input, expected_output) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Ok(expected_output), actual);
+    }
+  }
+
+  #[test]
+  fn equality_operators() {
+    let test_cases = vec![
+      (
+        "==",
+        vec![
+          Token::EqualEqual(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "= =",
+        vec![
+          Token::Equal(SourceSpan::new(1, 1, 1, 1)),
+          Token::Equal(SourceSpan::new(1, 3, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+      (
+        "=",
+        vec![
+          Token::Equal(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+    ];
+
+    for (input, expected_output) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Ok(expected_output), actual);
+    }
+  }
+
+  #[test]
+  fn division_operators() {
+    let test_cases = vec![
+      (
+        "//",
+        vec![
+          Token::SlashSlash(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "/",
+        vec![
+          Token::Slash(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      ("/* */", vec![Token::Eof(SourceSpan::new(1, 6, 1, 6))]),
+      (
+        "/ /",
+        vec![
+          Token::Slash(SourceSpan::new(1, 1, 1, 1)),
+          Token::Slash(SourceSpan::new(1, 3, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+      (
+        "///*x*/",
+        vec![
+          Token::SlashSlash(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 8, 1, 8)),
+        ],
+      ),
+    ];
+
+    for (input, expected_output) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Ok(expected_output), actual);
+    }
+  }
+
+  #[test]
+  fn assignment_operator() {
+    let test_cases = vec![
+      (
+        ":=",
+        vec![
+          Token::ColonEqual(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        ":",
+        vec![
+          Token::Colon(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        ": =",
+        vec![
+          Token::Colon(SourceSpan::new(1, 1, 1, 1)),
+          Token::Equal(SourceSpan::new(1, 3, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+    ];
+
+    for (input, expected_output) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Ok(expected_output), actual);
+    }
+  }
+
+  #[test]
+  fn statement_separators() {
+    let test_cases = vec![
+      (
+        ";",
+        vec![
+          Token::Semicolon(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ],
+      ),
+      (
+        "+ ; -",
+        vec![
+          Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+          Token::Semicolon(SourceSpan::new(1, 3, 1, 3)),
+          Token::Minus(SourceSpan::new(1, 5, 1, 5)),
+          Token::Eof(SourceSpan::new(1, 6, 1, 6)),
+        ],
+      ),
+    ];
+
+    for (input, expected_output) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Ok(expected_output), actual);
+    }
+  }
+
+  #[test]
+  fn dots() {
+    let test_cases = vec![
+      (
+        "x.y",
+        vec![
+          Token::Identifier("x".to_owned(), SourceSpan::new(1, 1, 1, 1)),
+          Token::Dot(SourceSpan::new(1, 2, 1, 2)),
+          Token::Identifier("y".to_owned(), SourceSpan::new(1, 3, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+      (
+        "..",
+        vec![
+          Token::DotDot(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "1..10",
+        vec![
+          Token::Integer(1, SourceSpan::new(1, 1, 1, 1)),
+          Token::DotDot(SourceSpan::new(1, 2, 1, 3)),
+          Token::Integer(10, SourceSpan::new(1, 4, 1, 5)),
+          Token::Eof(SourceSpan::new(1, 6, 1, 6)),
+        ],
+      ),
+      (
+        "3.25",
+        vec![
+          Token::RealLiteral(3.25, SourceSpan::new(1, 1, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+        ],
+      ),
+      (
+        "...",
+        vec![
+          Token::DotDot(SourceSpan::new(1, 1, 1, 2)),
+          Token::Dot(SourceSpan::new(1, 3, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+    ];
+
+    for (input, expected_output) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Ok(expected_output), actual);
+    }
+  }
+
+  #[test]
+  fn errors_on_unknown_tokens() {
+    let test_cases = vec![
+      (
+        "?",
+        vec![LexLuthorError::UnexpectedCharacter {
+          source_span: SourceSpan::new(1, 1, 1, 1),
+          message: "unexpected character ?".to_owned(),
+        }],
+      ),
+      (
+        "+-=/    ?",
+        vec![LexLuthorError::UnexpectedCharacter {
+          source_span: SourceSpan::new(1, 9, 1, 9),
+          message: "unexpected character ?".to_owned(),
+        }],
+      ),
+    ];
+
+    for (input, expected_error) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Err(expected_error), actual);
+    }
+  }
+
+  #[test]
+  fn embedded_nul_characters_are_not_the_end_of_input() {
+    assert_eq!(
+      Err(vec![LexLuthorError::UnexpectedCharacter {
+        source_span: SourceSpan::new(1, 2, 1, 2),
+        message: "unexpected character \\0".to_owned(),
+      }]),
+      LexLuthor::new("+\0-".to_owned()).lex()
+    );
+
+    let (tokens, _) = LexLuthor::new("+\0-".to_owned()).lex_with_recovery();
+
+    assert_eq!(
+      vec![
+        Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+        Token::Minus(SourceSpan::new(1, 3, 1, 3)),
+        Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+      ],
+      tokens
+    );
+  }
+
+  #[test]
+  fn recognizes_lines_and_columns() {
+    let test_cases = vec![
+      (
+        "+",
+        Ok(vec![
+          Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ]),
+      ),
+      (
+        "\n+",
+        Ok(vec![
+          Token::Plus(SourceSpan::new(2, 1, 2, 1)),
+          Token::Eof(SourceSpan::new(2, 2, 2, 2)),
+        ]),
+      ),
+      (
+        "+\n-",
+        Ok(vec![
+          Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+          Token::Minus(SourceSpan::new(2, 1, 2, 1)),
+          Token::Eof(SourceSpan::new(2, 2, 2, 2)),
+        ]),
+      ),
+      (
+        "\n\n\n     !",
+        Ok(vec![
+          Token::Bang(SourceSpan::new(4, 6, 4, 6)),
+          Token::Eof(SourceSpan::new(4, 7, 4, 7)),
+        ]),
+      ),
+      (
+        "!=\n  == &&",
+        Ok(vec![
+          Token::NotEqual(SourceSpan::new(1, 1, 1, 2)),
+          Token::EqualEqual(SourceSpan::new(2, 3, 2, 4)),
+          Token::AmpersandAmpersand(SourceSpan::new(2, 6, 2, 7)),
+          Token::Eof(SourceSpan::new(2, 8, 2, 8)),
+        ]),
+      ),
+      (
+        "+\r\n-",
+        Ok(vec![
+          Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+          Token::Minus(SourceSpan::new(2, 1, 2, 1)),
+          Token::Eof(SourceSpan::new(2, 2, 2, 2)),
+        ]),
+      ),
+      (
+        "+\r-\r\n\r\n*",
+        Ok(vec![
+          Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+          Token::Minus(SourceSpan::new(2, 1, 2, 1)),
+          Token::Star(SourceSpan::new(4, 1, 4, 1)),
+          Token::Eof(SourceSpan::new(4, 2, 4, 2)),
+        ]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(expected, actual);
+    }
+  }
+
+  #[test]
+  fn skips_line_comments() {
+    let test_cases = vec![
+      (
+        "+ # this is ignored\n-",
+        vec![
+          Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+          Token::Minus(SourceSpan::new(2, 1, 2, 1)),
+          Token::Eof(SourceSpan::new(2, 2, 2, 2)),
+        ],
+      ),
+      (
+        "# first\n# second\n+",
+        vec![
+          Token::Plus(SourceSpan::new(3, 1, 3, 1)),
+          Token::Eof(SourceSpan::new(3, 2, 3, 2)),
+        ],
+      ),
+      (
+        "+ # runs until the end of the input",
+        vec![
+          Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 36, 1, 36)),
+        ],
+      ),
+      ("#", vec![Token::Eof(SourceSpan::new(1, 2, 1, 2))]),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Ok(expected), actual);
+    }
+  }
+
+  #[test]
+  fn skips_block_comments() {
+    let test_cases = vec![
+      (
+        "+ /* this is ignored */ -",
+        Ok(vec![
+          Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+          Token::Minus(SourceSpan::new(1, 25, 1, 25)),
+          Token::Eof(SourceSpan::new(1, 26, 1, 26)),
+        ]),
+      ),
+      (
+        "/* this comment\nspans\nmultiple lines */+",
+        Ok(vec![
+          Token::Plus(SourceSpan::new(3, 18, 3, 18)),
+          Token::Eof(SourceSpan::new(3, 19, 3, 19)),
+        ]),
+      ),
+      ("/**/", Ok(vec![Token::Eof(SourceSpan::new(1, 5, 1, 5))])),
+      (
+        "+ /* never closed",
+        Err(vec![LexLuthorError::UnterminatedComment {
+          source_span: SourceSpan::new(1, 3, 1, 3),
+          message: "unterminated block comment, expected */".to_owned(),
+        }]),
+      ),
+      (
+        "\n/* never\nclosed",
+        Err(vec![LexLuthorError::UnterminatedComment {
+          source_span: SourceSpan::new(2, 1, 2, 1),
+          message: "unterminated block comment, expected */".to_owned(),
+        }]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(expected, actual);
+    }
+  }
+
+  #[test]
+  fn block_comments_nest() {
+    let test_cases = vec![
+      (
+        "+ /* outer /* inner */ still comment */ -",
+        Ok(vec![
+          Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+          Token::Minus(SourceSpan::new(1, 41, 1, 41)),
+          Token::Eof(SourceSpan::new(1, 42, 1, 42)),
+        ]),
+      ),
+      (
+        "/* 1 /* 2 /* 3 */ 2 */ 1 */+",
+        Ok(vec![
+          Token::Plus(SourceSpan::new(1, 28, 1, 28)),
+          Token::Eof(SourceSpan::new(1, 29, 1, 29)),
+        ]),
+      ),
+      (
+        "/*/**/*/+",
+        Ok(vec![
+          Token::Plus(SourceSpan::new(1, 9, 1, 9)),
+          Token::Eof(SourceSpan::new(1, 10, 1, 10)),
+        ]),
+      ),
+      (
+        "+\n  /* outer /* inner */ never closed",
+        Err(vec![LexLuthorError::UnterminatedComment {
+          source_span: SourceSpan::new(2, 3, 2, 3),
+          message: "unterminated block comment, expected */".to_owned(),
+        }]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(expected, actual);
+    }
+  }
+
+  #[test]
+  fn rejects_line_comment_prefixes_that_start_other_tokens() {
+    let test_cases = vec![
+      ("rem", "identifiers"),
+      ("_", "identifiers"),
+      ("\u{301}", "identifiers"),
+      ("1", "numbers"),
+      (".5", "numbers"),
+      ("\"", "strings"),
+      ("'", "character literals"),
+      (".", "numbers"),
+      ("/*", "block comments"),
+      ("/", "block comments"),
+      (" ", "whitespace"),
+      ("\t#", "whitespace"),
+      ("\n", "whitespace"),
+    ];
+
+    for (prefix, taken_over) in test_cases {
+      assert_eq!(
+        Err(format!(
+          "line comment prefix {:?} would start comments where {} start",
+          prefix, taken_over
+        )),
+        LexLuthor::new(String::new())
+          .with_line_comment_prefix(prefix)
+          .map(|_| ())
+      );
+    }
+
+    for prefix in &["", "#", "//", "--", ";", "..", "/-"] {
+      assert!(LexLuthor::new(String::new())
+        .with_line_comment_prefix(prefix)
+        .is_ok());
+    }
+  }
+
+  #[test]
+  fn line_comments_can_start_with_another_prefix() {
+    let lex = |input: &str, prefix: &str| {
+      LexLuthor::new(input.to_owned())
+        .with_line_comment_prefix(prefix)
+        .unwrap()
+        .lex()
+    };
+
+    assert_eq!(
+      Ok(vec![
+        Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+        Token::Minus(SourceSpan::new(2, 1, 2, 1)),
+        Token::Slash(SourceSpan::new(2, 3, 2, 3)),
+        Token::Eof(SourceSpan::new(2, 15, 2, 15)),
+      ]),
+      lex("+ // skipped\n- / // skipped", "//")
+    );
+    assert_eq!(
+      Ok(vec![
+        Token::Minus(SourceSpan::new(1, 1, 1, 1)),
+        Token::Plus(SourceSpan::new(2, 1, 2, 1)),
+        Token::Eof(SourceSpan::new(2, 2, 2, 2)),
+      ]),
+      lex("- -- skipped -- too\n+", "--")
+    );
+    assert_eq!(
+      Err(vec![LexLuthorError::UnexpectedCharacter {
+        source_span: SourceSpan::new(1, 1, 1, 1),
+        message: "unexpected character #".to_owned(),
+      }]),
+      lex("# no longer a comment", "--")
+    );
+    assert_eq!(
+      Ok(vec![
+        Token::Comment("-- kept".to_owned(), SourceSpan::new(1, 3, 1, 9)),
+        Token::Eof(SourceSpan::new(1, 10, 1, 10)),
+      ]),
+      LexLuthor::new("  -- kept".to_owned())
+        .with_line_comment_prefix("--")
+        .unwrap()
+        .preserving_comments(true)
+        .lex()
+    );
+  }
+
+  #[test]
+  fn preserves_comments_when_asked_to() {
+    let input = "+ # first\n/* second\nthird */ -";
+
+    let skipped = LexLuthor::new(input.to_owned()).lex().unwrap();
+    let preserved = LexLuthor::new(input.to_owned())
+      .preserving_comments(true)
+      .lex()
+      .unwrap();
+
+    assert_eq!(3, skipped.len());
+    assert_eq!(5, preserved.len());
+    assert_eq!(
+      vec![
+        Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+        Token::Comment("# first".to_owned(), SourceSpan::new(1, 3, 1, 9)),
+        Token::Comment(
+          "/* second\nthird */".to_owned(),
+          SourceSpan::new(2, 1, 3, 8)
+        ),
+        Token::Minus(SourceSpan::new(3, 10, 3, 10)),
+        Token::Eof(SourceSpan::new(3, 11, 3, 11)),
+      ],
+      preserved
+    );
+  }
+
+  #[test]
+  fn block_comments_span_every_line_they_cover() {
+    let actual = LexLuthor::new("+ /* one\ntwo\n  three */ -".to_owned())
+      .preserving_comments(true)
+      .lex();
+
+    assert_eq!(
+      Ok(vec![
+        Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+        Token::Comment(
+          "/* one\ntwo\n  three */".to_owned(),
+          SourceSpan::new(1, 3, 3, 10)
+        ),
+        Token::Minus(SourceSpan::new(3, 12, 3, 12)),
+        Token::Eof(SourceSpan::new(3, 13, 3, 13)),
+      ]),
+      actual
+    );
+  }
+
+  #[test]
+  fn tabs_advance_to_the_next_tab_stop() {
+    let lex = |input: &str, tab_width: usize| {
+      LexLuthor::new(input.to_owned())
+        .with_tab_width(tab_width)
+        .lex()
+        .unwrap()
+        .iter()
+        .map(Token::span)
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+      vec![SourceSpan::new(1, 5, 1, 5), SourceSpan::new(1, 6, 1, 6)],
+      lex("\t+", 4)
+    );
+    assert_eq!(
+      vec![
+        SourceSpan::new(1, 1, 1, 1),
+        SourceSpan::new(1, 9, 1, 9),
+        SourceSpan::new(1, 10, 1, 10)
+      ],
+      lex("-  \t\t+", 4)
+    );
+    assert_eq!(
+      vec![SourceSpan::new(1, 2, 1, 2), SourceSpan::new(1, 3, 1, 3)],
+      lex("\t+", 1)
+    );
+  }
+
+  #[test]
+  fn renders_diagnostics_with_wide_tabs() {
+    let mut lex_luthor = LexLuthor::new("\t+ \t?".to_owned()).with_tab_width(4);
+
+    let error = lex_luthor.lex().unwrap_err().remove(0);
+
+    assert_eq!(SourceSpan::new(1, 9, 1, 9), error.source_span());
+    assert_eq!(
+      "error: unexpected character ?\n --> 1:9\n  |\n1 | \t+ \t?\n  | \t  \t^",
+      lex_luthor.render_diagnostic(&error)
+    );
+  }
+
+  #[test]
+  fn line_comments_stop_before_carriage_returns() {
+    let actual = LexLuthor::new("# first\r\n-".to_owned())
+      .preserving_comments(true)
+      .lex();
+
+    assert_eq!(
+      Ok(vec![
+        Token::Comment("# first".to_owned(), SourceSpan::new(1, 1, 1, 7)),
+        Token::Minus(SourceSpan::new(2, 1, 2, 1)),
+        Token::Eof(SourceSpan::new(2, 2, 2, 2)),
+      ]),
+      actual
+    );
+  }
+
+  #[test]
+  fn keywords() {
+    let test_cases = vec![
+      (
+        "program",
+        vec![
+          Token::Program(SourceSpan::new(1, 1, 1, 7)),
+          Token::Eof(SourceSpan::new(1, 8, 1, 8)),
+        ],
+      ),
+      (
+        "define",
+        vec![
+          Token::Define(SourceSpan::new(1, 1, 1, 6)),
+          Token::Eof(SourceSpan::new(1, 7, 1, 7)),
+        ],
+      ),
+      (
+        "not",
+        vec![
+          Token::Not(SourceSpan::new(1, 1, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+      (
+        "variable",
+        vec![
+          Token::Variable(SourceSpan::new(1, 1, 1, 8)),
+          Token::Eof(SourceSpan::new(1, 9, 1, 9)),
+        ],
+      ),
+      (
+        "is",
+        vec![
+          Token::Is(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "natural",
+        vec![
+          Token::Natural(SourceSpan::new(1, 1, 1, 7)),
+          Token::Eof(SourceSpan::new(1, 8, 1, 8)),
+        ],
+      ),
+      (
+        "real",
+        vec![
+          Token::Real(SourceSpan::new(1, 1, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+        ],
+      ),
+      (
+        "char",
+        vec![
+          Token::Char(SourceSpan::new(1, 1, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+        ],
+      ),
+      (
+        "boolean",
+        vec![
+          Token::Boolean(SourceSpan::new(1, 1, 1, 7)),
+          Token::Eof(SourceSpan::new(1, 8, 1, 8)),
+        ],
+      ),
+      (
+        "execute",
+        vec![
+          Token::Execute(SourceSpan::new(1, 1, 1, 7)),
+          Token::Eof(SourceSpan::new(1, 8, 1, 8)),
+        ],
+      ),
+      (
+        "set",
+        vec![
+          Token::Set(SourceSpan::new(1, 1, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+      (
+        "get",
+        vec![
+          Token::Get(SourceSpan::new(1, 1, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+      (
+        "to",
+        vec![
+          Token::To(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "put",
+        vec![
+          Token::Put(SourceSpan::new(1, 1, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+      (
+        "loop",
+        vec![
+          Token::Loop(SourceSpan::new(1, 1, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+        ],
+      ),
+      (
+        "while",
+        vec![
+          Token::While(SourceSpan::new(1, 1, 1, 5)),
+          Token::Eof(SourceSpan::new(1, 6, 1, 6)),
+        ],
+      ),
+      (
+        "do",
+        vec![
+          Token::Do(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "true",
+        vec![
+          Token::True(SourceSpan::new(1, 1, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+        ],
+      ),
+      (
+        "false",
+        vec![
+          Token::False(SourceSpan::new(1, 1, 1, 5)),
+          Token::Eof(SourceSpan::new(1, 6, 1, 6)),
+        ],
+      ),
+      (
+        "if",
+        vec![
+          Token::If(SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "then",
+        vec![
+          Token::Then(SourceSpan::new(1, 1, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+        ],
+      ),
+      (
+        "else",
+        vec![
+          Token::Else(SourceSpan::new(1, 1, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+        ],
+      ),
+      (
+        "end",
+        vec![
+          Token::End(SourceSpan::new(1, 1, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+      (
+        "xor",
+        vec![
+          Token::Xor(SourceSpan::new(1, 1, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ],
+      ),
+      (
+        "constant",
+        vec![
+          Token::Constant(SourceSpan::new(1, 1, 1, 8)),
+          Token::Eof(SourceSpan::new(1, 9, 1, 9)),
+        ],
+      ),
+      (
+        "function",
+        vec![
+          Token::Function(SourceSpan::new(1, 1, 1, 8)),
+          Token::Eof(SourceSpan::new(1, 9, 1, 9)),
+        ],
+      ),
+      (
+        "return",
+        vec![
+          Token::Return(SourceSpan::new(1, 1, 1, 6)),
+          Token::Eof(SourceSpan::new(1, 7, 1, 7)),
+        ],
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Ok(expected), actual);
+    }
+  }
+
+  #[test]
+  fn integers() {
+    let test_cases = vec![
+      (
+        "0",
+        Ok(vec![
+          Token::Integer(0, SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ]),
+      ),
+      (
+        "42",
+        Ok(vec![
+          Token::Integer(42, SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ]),
+      ),
+      (
+        "42+1",
+        Ok(vec![
+          Token::Integer(42, SourceSpan::new(1, 1, 1, 2)),
+          Token::Plus(SourceSpan::new(1, 3, 1, 3)),
+          Token::Integer(1, SourceSpan::new(1, 4, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+        ]),
+      ),
+      (
+        "9223372036854775808",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 19),
+          message: "9223372036854775808 is not a valid integer, integers must be at most 9223372036854775807"
+            .to_owned(),
+        }]),
+      ),
+      (
+        "9223372036854775808 +",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 19),
+          message: "9223372036854775808 is not a valid integer, integers must be at most 9223372036854775807"
+            .to_owned(),
+        }]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(expected, actual);
+    }
+  }
+
+  #[test]
+  fn integers_with_a_radix_prefix() {
+    let test_cases = vec![
+      (
+        "0xFF",
+        Ok(vec![
+          Token::Integer(255, SourceSpan::new(1, 1, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+        ]),
+      ),
+      (
+        "0x0",
+        Ok(vec![
+          Token::Integer(0, SourceSpan::new(1, 1, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ]),
+      ),
+      (
+        "0X1f+1",
+        Ok(vec![
+          Token::Integer(31, SourceSpan::new(1, 1, 1, 4)),
+          Token::Plus(SourceSpan::new(1, 5, 1, 5)),
+          Token::Integer(1, SourceSpan::new(1, 6, 1, 6)),
+          Token::Eof(SourceSpan::new(1, 7, 1, 7)),
+        ]),
+      ),
+      (
+        "0x",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 2),
+          message: "0x is not a valid hexadecimal integer, 0x must be followed by hexadecimal digits"
+            .to_owned(),
+        }]),
+      ),
+      (
+        "0x1G +",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 4, 1, 4),
+          message: "0x1G is not a valid hexadecimal integer, G is not a hexadecimal digit".to_owned(),
+        }]),
+      ),
+      (
+        "0b101",
+        Ok(vec![
+          Token::Integer(5, SourceSpan::new(1, 1, 1, 5)),
+          Token::Eof(SourceSpan::new(1, 6, 1, 6)),
+        ]),
+      ),
+      (
+        "0B0",
+        Ok(vec![
+          Token::Integer(0, SourceSpan::new(1, 1, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ]),
+      ),
+      (
+        "0b",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 2),
+          message: "0b is not a valid binary integer, 0b must be followed by binary digits".to_owned(),
+        }]),
+      ),
+      (
+        "0b102",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 5, 1, 5),
+          message: "0b102 is not a valid binary integer, 2 is not a binary digit".to_owned(),
+        }]),
+      ),
+      (
+        "0x8000000000000000",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 18),
+          message: "0x8000000000000000 is not a valid integer, integers must be at most 9223372036854775807"
+            .to_owned(),
+        }]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(expected, actual);
+    }
+  }
+
+  #[test]
+  fn numbers_with_digit_separators() {
+    let test_cases = vec![
+      (
+        "1_000_000",
+        Ok(vec![
+          Token::Integer(1_000_000, SourceSpan::new(1, 1, 1, 9)),
+          Token::Eof(SourceSpan::new(1, 10, 1, 10)),
+        ]),
+      ),
+      (
+        "1_000.000_5",
+        Ok(vec![
+          Token::RealLiteral(1_000.000_5, SourceSpan::new(1, 1, 1, 11)),
+          Token::Eof(SourceSpan::new(1, 12, 1, 12)),
+        ]),
+      ),
+      (
+        "0xFF_FF",
+        Ok(vec![
+          Token::Integer(0xFF_FF, SourceSpan::new(1, 1, 1, 7)),
+          Token::Eof(SourceSpan::new(1, 8, 1, 8)),
+        ]),
+      ),
+      (
+        "_1",
+        Err(vec![LexLuthorError::InvalidIdentifier {
+          source_span: SourceSpan::new(1, 2, 1, 2),
+          identifier_source_span: SourceSpan::new(1, 1, 1, 2),
+          message: "_1 is not a valid identifier, _ must be followed by a letter".to_owned(),
+        }]),
+      ),
+      (
+        "1_",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 2),
+          message: "1_ is not a valid number, _ can only be used between digits".to_owned(),
+        }]),
+      ),
+      (
+        "1__2",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 4),
+          message: "1__2 is not a valid number, _ can only be used between digits".to_owned(),
+        }]),
+      ),
+      (
+        "1_.5",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 4),
+          message: "1_.5 is not a valid number, _ can only be used between digits".to_owned(),
+        }]),
+      ),
+      (
+        "0x_1",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 4),
+          message: "0x_1 is not a valid number, _ can only be used between digits".to_owned(),
+        }]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(expected, actual);
+    }
+  }
+
+  #[test]
+  fn numbers_cannot_be_followed_by_letters() {
+    let test_cases = vec![
+      (
+        "2x",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 2),
+          message: "2x is not a valid number, numbers cannot be followed by letters".to_owned(),
+        }]),
+      ),
+      (
+        "12.5abc +",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 7),
+          message: "12.5abc is not a valid number, numbers cannot be followed by letters"
+            .to_owned(),
+        }]),
+      ),
+      (
+        "2 x",
+        Ok(vec![
+          Token::Integer(2, SourceSpan::new(1, 1, 1, 1)),
+          Token::Identifier("x".to_owned(), SourceSpan::new(1, 3, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(expected, actual);
+    }
+  }
+
+  #[test]
+  fn reals() {
+    let test_cases = vec![
+      (
+        "12.75",
+        Ok(vec![
+          Token::RealLiteral(12.75, SourceSpan::new(1, 1, 1, 5)),
+          Token::Eof(SourceSpan::new(1, 6, 1, 6)),
+        ]),
+      ),
+      (
+        "0.5",
+        Ok(vec![
+          Token::RealLiteral(0.5, SourceSpan::new(1, 1, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ]),
+      ),
+      (
+        "3.",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 2),
+          message: "3. is not a valid real number, . must be followed by a digit".to_owned(),
+        }]),
+      ),
+      (
+        "3.1.4",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 5),
+          message: "3.1.4 is not a valid real number, real numbers can only have one .".to_owned(),
+        }]),
+      ),
+      (
+        ".5",
+        Err(vec![LexLuthorError::InvalidNumber {
+          source_span: SourceSpan::new(1, 1, 1, 2),
+          message: ".5 is not a valid real number, real numbers must start with a digit, try 0.5"
+            .to_owned(),
+        }]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(expected, actual);
+    }
+  }
+
+  #[test]
+  fn strings() {
+    let test_cases = vec![
+      (
+        r#""hello""#,
+        Ok(vec![
+          Token::String("hello".to_owned(), SourceSpan::new(1, 1, 1, 7)),
+          Token::Eof(SourceSpan::new(1, 8, 1, 8)),
+        ]),
+      ),
+      (
+        r#""""#,
+        Ok(vec![
+          Token::String("".to_owned(), SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ]),
+      ),
+      (
+        r#""a\nb\tc\\d\"e""#,
+        Ok(vec![
+          Token::String("a\nb\tc\\d\"e".to_owned(), SourceSpan::new(1, 1, 1, 15)),
+          Token::Eof(SourceSpan::new(1, 16, 1, 16)),
+        ]),
+      ),
+      (
+        r#""a\r\nb""#,
+        Ok(vec![
+          Token::String("a\r\nb".to_owned(), SourceSpan::new(1, 1, 1, 8)),
+          Token::Eof(SourceSpan::new(1, 9, 1, 9)),
+        ]),
+      ),
+      (
+        r#""never closed"#,
+        Err(vec![LexLuthorError::UnterminatedString {
+          source_span: SourceSpan::new(1, 1, 1, 1),
+          message: "unterminated string, expected \"".to_owned(),
+        }]),
+      ),
+      (
+        "+ \"broken\n+",
+        Err(vec![LexLuthorError::UnterminatedString {
+          source_span: SourceSpan::new(1, 3, 1, 3),
+          message: "unterminated string, expected \"".to_owned(),
+        }]),
+      ),
+      (
+        "\"a\rb",
+        Err(vec![LexLuthorError::UnterminatedString {
+          source_span: SourceSpan::new(1, 1, 1, 1),
+          message: "unterminated string, expected \"".to_owned(),
+        }]),
+      ),
+      (
+        r#""\q""#,
+        Err(vec![LexLuthorError::InvalidEscape {
+          source_span: SourceSpan::new(1, 2, 1, 2),
+          message: "unknown escape sequence \\q".to_owned(),
+        }]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(expected, actual);
+    }
+  }
+
+  #[test]
+  fn unicode_escapes() {
+    let test_cases = vec![
+      (
+        r#""\u{41}""#,
+        Ok(vec![
+          Token::String("A".to_owned(), SourceSpan::new(1, 1, 1, 8)),
+          Token::Eof(SourceSpan::new(1, 9, 1, 9)),
+        ]),
+      ),
+      (
+        r#""a\u{1F600}b\u{e9}""#,
+        Ok(vec![
+          Token::String("a\u{1F600}b\u{e9}".to_owned(), SourceSpan::new(1, 1, 1, 19)),
+          Token::Eof(SourceSpan::new(1, 20, 1, 20)),
+        ]),
+      ),
+      (
+        r"'\u{1F600}'",
+        Ok(vec![
+          Token::CharLiteral('\u{1F600}', SourceSpan::new(1, 1, 1, 11)),
+          Token::Eof(SourceSpan::new(1, 12, 1, 12)),
+        ]),
+      ),
+      (
+        r#""\u{110000}""#,
+        Err(vec![LexLuthorError::InvalidEscape {
+          source_span: SourceSpan::new(1, 2, 1, 11),
+          message: "\\u{110000} is not a valid Unicode code point".to_owned(),
+        }]),
+      ),
+      (
+        r#""\u{D800}""#,
+        Err(vec![LexLuthorError::InvalidEscape {
+          source_span: SourceSpan::new(1, 2, 1, 9),
+          message: "\\u{D800} is not a valid Unicode code point".to_owned(),
+        }]),
+      ),
+      (
+        r#""\u{}""#,
+        Err(vec![LexLuthorError::InvalidEscape {
+          source_span: SourceSpan::new(1, 2, 1, 5),
+          message:
+            "\\u{} is not a valid escape sequence, code points have between 1 and 6 hex digits"
+              .to_owned(),
+        }]),
+      ),
+      (
+        r#""\u41""#,
+        Err(vec![LexLuthorError::InvalidEscape {
+          source_span: SourceSpan::new(1, 2, 1, 3),
+          message: "\\u must be followed by a code point in braces, as in \\u{41}".to_owned(),
+        }]),
+      ),
+      (
+        r#""\u{4G}" +"#,
+        Err(vec![LexLuthorError::InvalidEscape {
+          source_span: SourceSpan::new(1, 2, 1, 5),
+          message: "unterminated escape sequence \\u{4, expected }".to_owned(),
+        }]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(expected, actual);
     }
+  }
 
-    if !errors.is_empty() {
-      Err(errors)
-    } else {
-      tokens.push(Token::Eof);
-      Ok(tokens)
+  #[test]
+  fn raw_strings() {
+    let test_cases = vec![
+      (
+        r#"r"a\n""#,
+        Ok(vec![
+          Token::String(r"a\n".to_owned(), SourceSpan::new(1, 1, 1, 6)),
+          Token::Eof(SourceSpan::new(1, 7, 1, 7)),
+        ]),
+      ),
+      (
+        "r\"C:\\x\\\ny\" r",
+        Ok(vec![
+          Token::String("C:\\x\\\ny".to_owned(), SourceSpan::new(1, 1, 2, 2)),
+          Token::Identifier("r".to_owned(), SourceSpan::new(2, 4, 2, 4)),
+          Token::Eof(SourceSpan::new(2, 5, 2, 5)),
+        ]),
+      ),
+      // A backslash can't escape the closing quote.
+      (
+        r#"r"a\" b"#,
+        Ok(vec![
+          Token::String(r"a\".to_owned(), SourceSpan::new(1, 1, 1, 5)),
+          Token::Identifier("b".to_owned(), SourceSpan::new(1, 7, 1, 7)),
+          Token::Eof(SourceSpan::new(1, 8, 1, 8)),
+        ]),
+      ),
+      (
+        r#"+ r"never closed"#,
+        Err(vec![LexLuthorError::UnterminatedString {
+          source_span: SourceSpan::new(1, 3, 1, 3),
+          message: "unterminated raw string, expected \"".to_owned(),
+        }]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(expected, actual);
     }
   }
-}
 
-#[cfg(test)]
-mod tests {
-  use super::*;
+  #[test]
+  fn char_literals() {
+    let test_cases = vec![
+      (
+        "'a'",
+        Ok(vec![
+          Token::CharLiteral('a', SourceSpan::new(1, 1, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ]),
+      ),
+      (
+        r"'\n'",
+        Ok(vec![
+          Token::CharLiteral('\n', SourceSpan::new(1, 1, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+        ]),
+      ),
+      (
+        r"'\''",
+        Ok(vec![
+          Token::CharLiteral('\'', SourceSpan::new(1, 1, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+        ]),
+      ),
+      (
+        "''",
+        Err(vec![LexLuthorError::InvalidCharLiteral {
+          source_span: SourceSpan::new(1, 1, 1, 1),
+          message: "empty character literal, character literals must contain exactly one character"
+            .to_owned(),
+        }]),
+      ),
+      (
+        "'ab'",
+        Err(vec![LexLuthorError::InvalidCharLiteral {
+          source_span: SourceSpan::new(1, 1, 1, 1),
+          message: "'ab' is not a valid character literal, character literals must contain exactly one character"
+            .to_owned(),
+        }]),
+      ),
+      (
+        "'a",
+        Err(vec![LexLuthorError::InvalidCharLiteral {
+          source_span: SourceSpan::new(1, 1, 1, 1),
+          message: "unterminated character literal, expected '".to_owned(),
+        }]),
+      ),
+      (
+        r"'\q'",
+        Err(vec![LexLuthorError::InvalidEscape {
+          source_span: SourceSpan::new(1, 2, 1, 2),
+          message: "unknown escape sequence \\q".to_owned(),
+        }]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(expected, actual);
+    }
+  }
 
   #[test]
-  fn recognizes_tokens() {
+  fn keywords_are_case_sensitive_by_default() {
+    let actual = LexLuthor::new("PROGRAM".to_owned()).lex();
+
+    assert_eq!(
+      Ok(vec![
+        Token::Identifier("PROGRAM".to_owned(), SourceSpan::new(1, 1, 1, 7)),
+        Token::Eof(SourceSpan::new(1, 8, 1, 8)),
+      ]),
+      actual
+    );
+  }
+
+  #[test]
+  fn case_insensitive_keywords() {
     let test_cases = vec![
       (
-        "{",
+        "PROGRAM",
         vec![
-          Token::LeftBrace(SourceSpan { line: 1, column: 1 }),
-          Token::Eof,
+          Token::Program(SourceSpan::new(1, 1, 1, 7)),
+          Token::Eof(SourceSpan::new(1, 8, 1, 8)),
         ],
       ),
       (
-        "}",
+        "Program",
         vec![
-          Token::RightBrace(SourceSpan { line: 1, column: 1 }),
-          Token::Eof,
+          Token::Program(SourceSpan::new(1, 1, 1, 7)),
+          Token::Eof(SourceSpan::new(1, 8, 1, 8)),
         ],
       ),
       (
-        "[",
+        "wHiLe",
         vec![
-          Token::LeftBracket(SourceSpan { line: 1, column: 1 }),
-          Token::Eof,
+          Token::While(SourceSpan::new(1, 1, 1, 5)),
+          Token::Eof(SourceSpan::new(1, 6, 1, 6)),
         ],
       ),
       (
-        "]",
+        "MyVariable",
         vec![
-          Token::RightBracket(SourceSpan { line: 1, column: 1 }),
-          Token::Eof,
+          Token::Identifier("MyVariable".to_owned(), SourceSpan::new(1, 1, 1, 10)),
+          Token::Eof(SourceSpan::new(1, 11, 1, 11)),
         ],
       ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned())
+        .with_case_insensitive_keywords(true)
+        .lex();
+
+      assert_eq!(Ok(expected), actual);
+    }
+  }
+
+  #[test]
+  fn identifiers() {
+    let test_cases = vec![
       (
-        ",",
-        vec![Token::Comma(SourceSpan { line: 1, column: 1 }), Token::Eof],
+        "x",
+        Ok(vec![
+          Token::Identifier("x".to_owned(), SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ]),
       ),
       (
-        "+",
-        vec![Token::Plus(SourceSpan { line: 1, column: 1 }), Token::Eof],
+        "_x",
+        Ok(vec![
+          Token::Identifier("_x".to_owned(), SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ]),
       ),
       (
-        "-",
-        vec![Token::Minus(SourceSpan { line: 1, column: 1 }), Token::Eof],
+        "_",
+        Ok(vec![
+          Token::Identifier("_".to_owned(), SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ]),
       ),
       (
-        "/",
-        vec![Token::Slash(SourceSpan { line: 1, column: 1 }), Token::Eof],
+        "x__",
+        Err(vec![LexLuthorError::InvalidIdentifier {
+          source_span: SourceSpan::new(1, 3, 1, 3),
+          identifier_source_span: SourceSpan::new(1, 1, 1, 3),
+          message: "x__ is not a valid identifier, _ must be followed by a letter".to_owned(),
+        }]),
       ),
       (
-        "*",
-        vec![Token::Star(SourceSpan { line: 1, column: 1 }), Token::Eof],
+        "x2",
+        Err(vec![LexLuthorError::InvalidIdentifier {
+          source_span: SourceSpan::new(1, 2, 1, 2),
+          identifier_source_span: SourceSpan::new(1, 1, 1, 2),
+          message: "x2 is not a valid identifier, 2 must be followed by a letter".to_owned(),
+        }]),
       ),
       (
-        "**",
+        "x2y_z2w",
+        Ok(vec![
+          Token::Identifier("x2y_z2w".to_owned(), SourceSpan::new(1, 1, 1, 7)),
+          Token::Eof(SourceSpan::new(1, 8, 1, 8)),
+        ]),
+      ),
+      (
+        "__",
+        Err(vec![LexLuthorError::InvalidIdentifier {
+          source_span: SourceSpan::new(1, 2, 1, 2),
+          identifier_source_span: SourceSpan::new(1, 1, 1, 2),
+          message: "__ is not a valid identifier, _ must be followed by a letter".to_owned(),
+        }]),
+      ),
+      (
+        "__variable_name",
+        Err(vec![LexLuthorError::InvalidIdentifier {
+          source_span: SourceSpan::new(1, 2, 1, 2),
+          identifier_source_span: SourceSpan::new(1, 1, 1, 15),
+          message: "__variable_name is not a valid identifier, _ must be followed by a letter"
+            .to_owned(),
+        }]),
+      ),
+      (
+        "+ a_1b",
+        Err(vec![LexLuthorError::InvalidIdentifier {
+          source_span: SourceSpan::new(1, 5, 1, 5),
+          identifier_source_span: SourceSpan::new(1, 3, 1, 6),
+          message: "a_1b is not a valid identifier, _ must be followed by a letter".to_owned(),
+        }]),
+      ),
+      (
+        "x2 +",
+        Err(vec![LexLuthorError::InvalidIdentifier {
+          source_span: SourceSpan::new(1, 2, 1, 2),
+          identifier_source_span: SourceSpan::new(1, 1, 1, 2),
+          message: "x2 is not a valid identifier, 2 must be followed by a letter".to_owned(),
+        }]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(expected, actual);
+    }
+  }
+
+  #[test]
+  fn underscores_must_be_followed_by_a_letter_unless_they_stand_alone() {
+    let test_cases = vec![
+      (
+        "_",
+        Ok(vec![
+          Token::Identifier("_".to_owned(), SourceSpan::new(1, 1, 1, 1)),
+          Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+        ]),
+      ),
+      (
+        "a_",
+        Err(vec![LexLuthorError::InvalidIdentifier {
+          source_span: SourceSpan::new(1, 2, 1, 2),
+          identifier_source_span: SourceSpan::new(1, 1, 1, 2),
+          message: "a_ is not a valid identifier, _ must be followed by a letter".to_owned(),
+        }]),
+      ),
+      (
+        "_a",
+        Ok(vec![
+          Token::Identifier("_a".to_owned(), SourceSpan::new(1, 1, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ]),
+      ),
+      (
+        "a_b",
+        Ok(vec![
+          Token::Identifier("a_b".to_owned(), SourceSpan::new(1, 1, 1, 3)),
+          Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+        ]),
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      assert_eq!(expected, LexLuthor::new(input.to_owned()).lex());
+    }
+  }
+
+  #[test]
+  fn identifiers_can_use_unicode_letters() {
+    let test_cases = vec![
+      (
+        "café",
+        vec![
+          Token::Identifier("café".to_owned(), SourceSpan::new(1, 1, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+        ],
+      ),
+      (
+        "naïve + x",
+        vec![
+          Token::Identifier("naïve".to_owned(), SourceSpan::new(1, 1, 1, 5)),
+          Token::Plus(SourceSpan::new(1, 7, 1, 7)),
+          Token::Identifier("x".to_owned(), SourceSpan::new(1, 9, 1, 9)),
+          Token::Eof(SourceSpan::new(1, 10, 1, 10)),
+        ],
+      ),
+      // e followed by a combining acute accent.
+      (
+        "cafe\u{301}",
+        vec![
+          Token::Identifier("cafe\u{301}".to_owned(), SourceSpan::new(1, 1, 1, 5)),
+          Token::Eof(SourceSpan::new(1, 6, 1, 6)),
+        ],
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      assert_eq!(Ok(expected), LexLuthor::new(input.to_owned()).lex());
+    }
+  }
+
+  #[test]
+  fn identifiers_cannot_start_with_a_combining_mark() {
+    assert_eq!(
+      Err(vec![LexLuthorError::InvalidIdentifier {
+        source_span: SourceSpan::new(1, 3, 1, 3),
+        identifier_source_span: SourceSpan::new(1, 3, 1, 4),
+        message:
+          "\\u{301}\u{308} is not a valid identifier, identifiers must start with a letter or _"
+            .to_owned(),
+      }]),
+      LexLuthor::new("+ \u{301}\u{308} -".to_owned()).lex()
+    );
+  }
+
+  #[test]
+  fn identifiers_can_be_limited_in_length() {
+    let lex = |input: &str| {
+      LexLuthor::new(input.to_owned())
+        .with_max_identifier_length(4)
+        .lex()
+    };
+
+    assert_eq!(
+      Ok(vec![
+        Token::Identifier("abcd".to_owned(), SourceSpan::new(1, 1, 1, 4)),
+        Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+      ]),
+      lex("abcd")
+    );
+    assert_eq!(
+      Err(vec![LexLuthorError::IdentifierTooLong {
+        source_span: SourceSpan::new(1, 3, 1, 3),
+        message: "abcde is not a valid identifier, identifiers can be at most 4 characters long"
+          .to_owned(),
+      }]),
+      lex("+ abcde -")
+    );
+    assert!(LexLuthor::new("abcde".to_owned()).lex().is_ok());
+  }
+
+  #[test]
+  fn identifiers_do_not_swallow_the_next_character() {
+    let test_cases = vec![
+      (
+        "x+",
+        vec![
+          Token::Identifier("x".to_owned(), SourceSpan::new(1, 1, 1, 1)),
+          Token::Plus(SourceSpan::new(1, 2, 1, 2)),
+          Token::Eof(SourceSpan::new(1, 3, 1, 3)),
+        ],
+      ),
+      (
+        "abc<=d",
+        vec![
+          Token::Identifier("abc".to_owned(), SourceSpan::new(1, 1, 1, 3)),
+          Token::LessThanOrEqual(SourceSpan::new(1, 4, 1, 5)),
+          Token::Identifier("d".to_owned(), SourceSpan::new(1, 6, 1, 6)),
+          Token::Eof(SourceSpan::new(1, 7, 1, 7)),
+        ],
+      ),
+      (
+        "if(x)",
+        vec![
+          Token::If(SourceSpan::new(1, 1, 1, 2)),
+          Token::LeftParen(SourceSpan::new(1, 3, 1, 3)),
+          Token::Identifier("x".to_owned(), SourceSpan::new(1, 4, 1, 4)),
+          Token::RightParen(SourceSpan::new(1, 5, 1, 5)),
+          Token::Eof(SourceSpan::new(1, 6, 1, 6)),
+        ],
+      ),
+      (
+        "x\"s\"",
+        vec![
+          Token::Identifier("x".to_owned(), SourceSpan::new(1, 1, 1, 1)),
+          Token::String("s".to_owned(), SourceSpan::new(1, 2, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+        ],
+      ),
+      (
+        "'c'x/**/y",
         vec![
-          Token::StarStar(SourceSpan { line: 1, column: 2 }),
-          Token::Eof,
+          Token::CharLiteral('c', SourceSpan::new(1, 1, 1, 3)),
+          Token::Identifier("x".to_owned(), SourceSpan::new(1, 4, 1, 4)),
+          Token::Identifier("y".to_owned(), SourceSpan::new(1, 9, 1, 9)),
+          Token::Eof(SourceSpan::new(1, 10, 1, 10)),
         ],
       ),
+    ];
+
+    for (input, expected_output) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Ok(expected_output), actual);
+    }
+  }
+
+  #[test]
+  fn lexes_large_inputs_in_linear_time() {
+    let input = "+".repeat(100_000);
+
+    let tokens = LexLuthor::new(input).lex().unwrap();
+
+    assert_eq!(100_001, tokens.len());
+    assert_eq!(
+      Some(&Token::Plus(SourceSpan::new(1, 100_000, 1, 100_000))),
+      tokens.get(99_999)
+    );
+  }
+
+  #[test]
+  fn lexes_multi_byte_characters() {
+    let test_cases = vec![
       (
-        "%",
+        "+ # comentário em português\n-",
         vec![
-          Token::Percent(SourceSpan { line: 1, column: 1 }),
-          Token::Eof,
+          Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+          Token::Minus(SourceSpan::new(2, 1, 2, 1)),
+          Token::Eof(SourceSpan::new(2, 2, 2, 2)),
         ],
       ),
       (
-        "%%",
+        "/* 🦀🦀🦀 */ +",
         vec![
-          Token::PercentPercent(SourceSpan { line: 1, column: 2 }),
-          Token::Eof,
+          Token::Plus(SourceSpan::new(1, 11, 1, 11)),
+          Token::Eof(SourceSpan::new(1, 12, 1, 12)),
         ],
       ),
       (
-        "=",
-        vec![Token::Equal(SourceSpan { line: 1, column: 1 }), Token::Eof],
-      ),
-      (
-        "!",
-        vec![Token::Bang(SourceSpan { line: 1, column: 1 }), Token::Eof],
-      ),
-      (
-        "<",
+        "ação",
         vec![
-          Token::LessThan(SourceSpan { line: 1, column: 1 }),
-          Token::Eof,
+          Token::Identifier("ação".to_owned(), SourceSpan::new(1, 1, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
         ],
       ),
       (
-        "<=",
+        "\"日本\"",
         vec![
-          Token::LessThanOrEqual(SourceSpan { line: 1, column: 2 }),
-          Token::Eof,
+          Token::String("日本".to_owned(), SourceSpan::new(1, 1, 1, 4)),
+          Token::Eof(SourceSpan::new(1, 5, 1, 5)),
         ],
       ),
+    ];
+
+    for (input, expected) in test_cases {
+      let actual = LexLuthor::new(input.to_owned()).lex();
+
+      assert_eq!(Ok(expected), actual);
+    }
+  }
+
+  #[test]
+  fn iterator_yields_the_same_tokens_as_lex() {
+    let input = "(1 + 2.5) ** 3 # comment\n<= 10 %% 4";
+
+    let expected = LexLuthor::new(input.to_owned()).lex();
+
+    let actual: Result<Vec<Token>, LexLuthorError> = LexLuthor::new(input.to_owned()).collect();
+
+    assert_eq!(expected, actual.map_err(|error| vec![error]));
+  }
+
+  #[test]
+  fn keeps_returning_eof_past_the_end() {
+    for input in ["+\n", "", "?", "/* unterminated", "\"unterminated"] {
+      let mut lex_luthor = LexLuthor::new(input.to_owned());
+
+      let eof = loop {
+        if let Ok(token @ Token::Eof(_)) = lex_luthor.next_token() {
+          break token;
+        }
+      };
+
+      for _ in 0..5 {
+        assert_eq!(Ok(eof.clone()), lex_luthor.next_token());
+      }
+    }
+  }
+
+  #[test]
+  fn iterator_stops_after_eof() {
+    let mut lex_luthor = LexLuthor::new("+".to_owned());
+
+    assert_eq!(
+      Some(Ok(Token::Plus(SourceSpan::new(1, 1, 1, 1)))),
+      lex_luthor.next()
+    );
+    assert_eq!(
+      Some(Ok(Token::Eof(SourceSpan::new(1, 2, 1, 2)))),
+      lex_luthor.next()
+    );
+    assert_eq!(None, lex_luthor.next());
+  }
+
+  #[test]
+  fn iterator_yields_errors_in_place() {
+    let actual: Vec<Result<Token, LexLuthorError>> = LexLuthor::new("+ ? -".to_owned()).collect();
+
+    assert_eq!(
+      vec![
+        Ok(Token::Plus(SourceSpan::new(1, 1, 1, 1))),
+        Err(LexLuthorError::UnexpectedCharacter {
+          source_span: SourceSpan::new(1, 3, 1, 3),
+          message: "unexpected character ?".to_owned(),
+        }),
+        Ok(Token::Minus(SourceSpan::new(1, 5, 1, 5))),
+        Ok(Token::Eof(SourceSpan::new(1, 6, 1, 6))),
+      ],
+      actual
+    );
+  }
+
+  #[test]
+  fn spans_cover_the_whole_lexeme() {
+    let actual = LexLuthor::new("** abc 42\n\"hi\"".to_owned()).lex();
+
+    assert_eq!(
+      Ok(vec![
+        Token::StarStar(SourceSpan::new(1, 1, 1, 2)),
+        Token::Identifier("abc".to_owned(), SourceSpan::new(1, 4, 1, 6)),
+        Token::Integer(42, SourceSpan::new(1, 8, 1, 9)),
+        Token::String("hi".to_owned(), SourceSpan::new(2, 1, 2, 4)),
+        Token::Eof(SourceSpan::new(2, 5, 2, 5)),
+      ]),
+      actual
+    );
+  }
+
+  #[test]
+  fn errors_display_their_position_and_message() {
+    let test_cases = vec![
+      ("\n\n    ?", "line 3, column 5: unexpected character ?"),
       (
-        ">",
-        vec![
-          Token::GreaterThan(SourceSpan { line: 1, column: 1 }),
-          Token::Eof,
-        ],
+        "x__",
+        "line 1, column 3: x__ is not a valid identifier, _ must be followed by a letter",
       ),
+    ];
+
+    for (input, expected) in test_cases {
+      let errors = LexLuthor::new(input.to_owned()).lex().unwrap_err();
+
+      assert_eq!(expected, errors[0].to_string());
+    }
+  }
+
+  #[test]
+  fn errors_can_be_boxed() {
+    fn lex(input: &str) -> Result<Vec<Token>, Box<dyn std::error::Error>> {
+      let mut tokens = Vec::new();
+
+      for token in LexLuthor::new(input.to_owned()) {
+        tokens.push(token?);
+      }
+
+      Ok(tokens)
+    }
+
+    assert_eq!(
+      "line 1, column 3: unexpected character ?",
+      lex("+ ?").unwrap_err().to_string()
+    );
+  }
+
+  #[test]
+  fn errors_can_be_cloned() {
+    let errors = LexLuthor::new("? x__".to_owned()).lex().unwrap_err();
+
+    assert_eq!(errors, errors.clone());
+  }
+
+  #[test]
+  fn lexes_borrowed_source_code_like_owned_source_code() {
+    let source_code = "program\n  x := 0x1F + y # comment\nend";
+    let slice = &source_code[10..23];
+
+    let borrowed = LexLuthor::new_borrowed(slice).lex();
+
+    assert_eq!(LexLuthor::new(slice.to_owned()).lex(), borrowed);
+    assert_eq!(
+      Ok(vec![
+        Token::Identifier("x".to_owned(), SourceSpan::new(1, 1, 1, 1)),
+        Token::ColonEqual(SourceSpan::new(1, 3, 1, 4)),
+        Token::Integer(31, SourceSpan::new(1, 6, 1, 9)),
+        Token::Plus(SourceSpan::new(1, 11, 1, 11)),
+        Token::Identifier("y".to_owned(), SourceSpan::new(1, 13, 1, 13)),
+        Token::Eof(SourceSpan::new(1, 14, 1, 14)),
+      ]),
+      borrowed
+    );
+  }
+
+  #[test]
+  fn newlines_are_only_tokens_when_significant() {
+    assert_eq!(
+      Ok(vec![
+        Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+        Token::Minus(SourceSpan::new(3, 1, 3, 1)),
+        Token::Eof(SourceSpan::new(3, 2, 3, 2)),
+      ]),
+      LexLuthor::new("+\n\n-".to_owned()).lex()
+    );
+    assert_eq!(
+      Ok(vec![
+        Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+        Token::Newline(SourceSpan::new(1, 2, 2, 1)),
+        Token::Minus(SourceSpan::new(3, 1, 3, 1)),
+        Token::Eof(SourceSpan::new(3, 2, 3, 2)),
+      ]),
+      LexLuthor::new("+\n\n-".to_owned())
+        .with_significant_newlines(true)
+        .lex()
+    );
+  }
+
+  #[test]
+  fn significant_newlines_fold_blank_lines() {
+    assert_eq!(
+      Ok(vec![
+        Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+        Token::Newline(SourceSpan::new(1, 4, 2, 4)),
+        Token::Minus(SourceSpan::new(3, 2, 3, 2)),
+        Token::Newline(SourceSpan::new(3, 3, 3, 3)),
+        Token::Eof(SourceSpan::new(4, 1, 4, 1)),
+      ]),
+      LexLuthor::new("+ \t\n  \r\n -\n".to_owned())
+        .with_significant_newlines(true)
+        .lex()
+    );
+  }
+
+  #[test]
+  fn warns_on_mixed_indentation_only_when_enabled() {
+    let mut lex_luthor = LexLuthor::new(" \t+".to_owned());
+
+    assert_eq!(
+      Ok(vec![
+        Token::Plus(SourceSpan::new(1, 3, 1, 3)),
+        Token::Eof(SourceSpan::new(1, 4, 1, 4)),
+      ]),
+      lex_luthor.lex()
+    );
+    assert!(lex_luthor.warnings().is_empty());
+
+    let mut lex_luthor =
+      LexLuthor::new("-\n \t+ \t-\n\t\t-".to_owned()).warn_on_mixed_indentation(true);
+
+    assert_eq!(
+      Ok(vec![
+        Token::Minus(SourceSpan::new(1, 1, 1, 1)),
+        Token::Plus(SourceSpan::new(2, 3, 2, 3)),
+        Token::Minus(SourceSpan::new(2, 6, 2, 6)),
+        Token::Minus(SourceSpan::new(3, 3, 3, 3)),
+        Token::Eof(SourceSpan::new(3, 4, 3, 4)),
+      ]),
+      lex_luthor.lex()
+    );
+    assert_eq!(
+      &[LexLuthorWarning::MixedIndentation {
+        source_span: SourceSpan::new(2, 1, 2, 2),
+        message: "indentation mixes tabs and spaces".to_owned(),
+      }],
+      lex_luthor.warnings()
+    );
+
+    let mut lex_luthor = LexLuthor::new("+\n \t-".to_owned())
+      .with_significant_newlines(true)
+      .warn_on_mixed_indentation(true);
+
+    assert_eq!(
+      Ok(vec![
+        Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+        Token::Newline(SourceSpan::new(1, 2, 1, 2)),
+        Token::Minus(SourceSpan::new(2, 3, 2, 3)),
+        Token::Eof(SourceSpan::new(2, 4, 2, 4)),
+      ]),
+      lex_luthor.lex()
+    );
+    assert_eq!(
+      &[LexLuthorWarning::MixedIndentation {
+        source_span: SourceSpan::new(2, 1, 2, 2),
+        message: "indentation mixes tabs and spaces".to_owned(),
+      }],
+      lex_luthor.warnings()
+    );
+  }
+
+  #[test]
+  fn warns_about_deprecated_operators_only_when_asked_to() {
+    let mut lex_luthor = LexLuthor::new("&".to_owned());
+
+    assert_eq!(
+      Ok(vec![
+        Token::Ampersand(SourceSpan::new(1, 1, 1, 1)),
+        Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+      ]),
+      lex_luthor.lex()
+    );
+    assert!(lex_luthor.warnings().is_empty());
+
+    let mut lex_luthor = LexLuthor::new("&".to_owned()).with_deprecations(true);
+
+    assert_eq!(
+      Ok(vec![
+        Token::Ampersand(SourceSpan::new(1, 1, 1, 1)),
+        Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+      ]),
+      lex_luthor.lex()
+    );
+    assert_eq!(
+      &[LexLuthorWarning::DeprecatedOperator {
+        source_span: SourceSpan::new(1, 1, 1, 1),
+        message: "& is deprecated, use && instead".to_owned(),
+      }],
+      lex_luthor.warnings()
+    );
+  }
+
+  #[test]
+  fn deprecation_warnings_cover_every_single_character_operator() {
+    let mut lex_luthor = LexLuthor::new("a && b | c & d || e".to_owned()).with_deprecations(true);
+
+    assert!(lex_luthor.lex().is_ok());
+    assert_eq!(
+      vec![
+        "line 1, column 8: | is deprecated, use || instead",
+        "line 1, column 12: & is deprecated, use && instead",
+      ],
+      lex_luthor
+        .warnings()
+        .iter()
+        .map(|warning| warning.to_string())
+        .collect::<Vec<_>>()
+    );
+
+    lex_luthor.reset("a | b".to_owned());
+
+    assert!(lex_luthor.warnings().is_empty());
+  }
+
+  #[test]
+  fn embedders_can_add_keywords() {
+    assert_eq!(
+      Ok(vec![
+        Token::Identifier("match".to_owned(), SourceSpan::new(1, 1, 1, 5)),
+        Token::Eof(SourceSpan::new(1, 6, 1, 6)),
+      ]),
+      LexLuthor::new("match".to_owned()).lex()
+    );
+    assert_eq!(
+      Ok(vec![
+        Token::Keyword("match".to_owned(), SourceSpan::new(1, 1, 1, 5)),
+        Token::Identifier("x".to_owned(), SourceSpan::new(1, 7, 1, 7)),
+        Token::Keyword("case".to_owned(), SourceSpan::new(1, 9, 1, 12)),
+        Token::If(SourceSpan::new(1, 14, 1, 15)),
+        Token::Eof(SourceSpan::new(1, 16, 1, 16)),
+      ]),
+      LexLuthor::new("match x CASE if".to_owned())
+        .with_case_insensitive_keywords(true)
+        .with_extra_keywords(&["match", "case"])
+        .lex()
+    );
+
+    let expected = Ok(vec![
+      Token::Keyword("match".to_owned(), SourceSpan::new(1, 1, 1, 5)),
+      Token::Keyword("match".to_owned(), SourceSpan::new(1, 7, 1, 11)),
+      Token::Keyword("match".to_owned(), SourceSpan::new(1, 13, 1, 17)),
+      Token::Eof(SourceSpan::new(1, 18, 1, 18)),
+    ]);
+
+    assert_eq!(
+      expected,
+      LexLuthor::new("MATCH match Match".to_owned())
+        .with_case_insensitive_keywords(true)
+        .with_extra_keywords(&["Match"])
+        .lex()
+    );
+    assert_eq!(
+      expected,
+      LexLuthor::new("MATCH match Match".to_owned())
+        .with_extra_keywords(&["Match"])
+        .with_case_insensitive_keywords(true)
+        .lex()
+    );
+    assert_eq!(
+      Ok(vec![
+        Token::Identifier("match".to_owned(), SourceSpan::new(1, 1, 1, 5)),
+        Token::Keyword("Match".to_owned(), SourceSpan::new(1, 7, 1, 11)),
+        Token::Eof(SourceSpan::new(1, 12, 1, 12)),
+      ]),
+      LexLuthor::new("match Match".to_owned())
+        .with_extra_keywords(&["Match"])
+        .lex()
+    );
+  }
+
+  #[test]
+  fn counts_tokens_like_lex() {
+    assert_eq!(Ok(4), LexLuthor::new("+ - *".to_owned()).count_tokens());
+
+    for input in &["", "program x := 1 end", "# only a comment", "+ ? -"] {
+      assert_eq!(
+        LexLuthor::new((*input).to_owned())
+          .lex()
+          .map(|tokens| tokens.len()),
+        LexLuthor::new((*input).to_owned()).count_tokens()
+      );
+    }
+  }
+
+  #[test]
+  fn maps_spans_to_byte_offsets() {
+    let source_code = "program\r\n  café := \"ü\"\n\tnaïve";
+    let mut lex_luthor = LexLuthor::new(source_code.to_owned()).with_tab_width(4);
+
+    let tokens = lex_luthor.lex().unwrap();
+
+    assert_eq!(
+      Some(source_code.find("café").unwrap()),
+      lex_luthor.byte_offset(2, 3)
+    );
+
+    let byte_ranges: Vec<_> = tokens
+      .iter()
+      .map(|token| lex_luthor.byte_range(&token.span()).unwrap())
+      .map(|range| &source_code[range])
+      .collect();
+
+    assert_eq!(
+      vec!["program", "café", ":=", "\"ü\"", "naïve", ""],
+      byte_ranges
+    );
+    assert_eq!(None, lex_luthor.byte_offset(4, 1));
+    assert_eq!(None, lex_luthor.byte_offset(1, 20));
+  }
+
+  #[test]
+  fn errors_can_be_combined_into_one() {
+    fn count_operators(input: &str) -> Result<usize, LexLuthorErrors> {
+      let tokens = LexLuthor::new(input.to_owned()).lex()?;
+
+      Ok(tokens.iter().filter(|token| token.is_operator()).count())
+    }
+
+    assert_eq!(Ok(2), count_operators("1 + 2 * 3"));
+
+    let errors = count_operators("1 ? 2\n$").unwrap_err();
+
+    assert_eq!(2, errors.0.len());
+    assert_eq!(
+      "line 1, column 3: unexpected character ?\nline 2, column 1: unexpected character $",
+      errors.to_string()
+    );
+  }
+
+  #[test]
+  fn question_marks_are_only_tokens_when_asked_for() {
+    assert_eq!(
+      Err(vec![LexLuthorError::UnexpectedCharacter {
+        source_span: SourceSpan::new(1, 1, 1, 1),
+        message: "unexpected character ?".to_owned(),
+      }]),
+      LexLuthor::new("?".to_owned()).lex()
+    );
+    assert_eq!(
+      Ok(vec![
+        Token::Question(SourceSpan::new(1, 1, 1, 1)),
+        Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+      ]),
+      LexLuthor::new("?".to_owned())
+        .with_question_token(true)
+        .lex()
+    );
+  }
+
+  #[test]
+  fn skips_a_leading_byte_order_mark() {
+    assert_eq!(
+      Ok(vec![
+        Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+        Token::Eof(SourceSpan::new(1, 2, 1, 2)),
+      ]),
+      LexLuthor::new("\u{FEFF}+".to_owned()).lex()
+    );
+
+    let from_reader = LexLuthor::from_reader("\u{FEFF}x\n-".as_bytes())
+      .unwrap()
+      .lex();
+
+    assert_eq!(
+      Ok(vec![
+        Token::Identifier("x".to_owned(), SourceSpan::new(1, 1, 1, 1)),
+        Token::Minus(SourceSpan::new(2, 1, 2, 1)),
+        Token::Eof(SourceSpan::new(2, 2, 2, 2)),
+      ]),
+      from_reader
+    );
+
+    // Only a leading byte order mark is skipped.
+    assert_eq!(
+      Err(vec![LexLuthorError::UnexpectedCharacter {
+        source_span: SourceSpan::new(1, 2, 1, 2),
+        message: "unexpected character \\u{feff}".to_owned(),
+      }]),
+      LexLuthor::new("+\u{FEFF}".to_owned()).lex()
+    );
+  }
+
+  #[test]
+  fn snippets_report_positions_in_the_whole_document() {
+    let mut lex_luthor = LexLuthor::new_at("x + ?\n  y".to_owned(), 10, 5);
+
+    let (tokens, errors) = lex_luthor.lex_with_recovery();
+
+    assert_eq!(
+      vec![
+        Token::Identifier("x".to_owned(), SourceSpan::new(10, 5, 10, 5)),
+        Token::Plus(SourceSpan::new(10, 7, 10, 7)),
+        Token::Identifier("y".to_owned(), SourceSpan::new(11, 3, 11, 3)),
+        Token::Eof(SourceSpan::new(11, 4, 11, 4)),
+      ],
+      tokens
+    );
+    assert_eq!(SourceSpan::new(10, 9, 10, 9), errors[0].source_span());
+    assert_eq!(Some(2), lex_luthor.byte_offset(10, 7));
+    assert_eq!(Some(8), lex_luthor.byte_offset(11, 3));
+    assert_eq!(None, lex_luthor.byte_offset(9, 1));
+  }
+
+  #[test]
+  fn byte_ranges_cover_both_halves_of_a_crlf() {
+    let mut lex_luthor = LexLuthor::new("\r\n0".to_owned()).with_significant_newlines(true);
+
+    let tokens = lex_luthor.lex().unwrap();
+
+    assert_eq!(Token::Newline(SourceSpan::new(1, 1, 1, 2)), tokens[0]);
+    assert_eq!(Some(0..2), lex_luthor.byte_range(&tokens[0].span()));
+    assert_eq!(Some(2..3), lex_luthor.byte_range(&tokens[1].span()));
+  }
+
+  #[test]
+  fn identifiers_can_start_at_the_first_byte() {
+    let mut lex_luthor = LexLuthor::new("abc".to_owned());
+
+    // The state every lexer is in before reading its first character.
+    lex_luthor.position = 0;
+    lex_luthor.column = 0;
+    lex_luthor.character = '\0';
+
+    assert_eq!(0, lex_luthor.character_position());
+
+    lex_luthor.read_character();
+
+    assert_eq!(0, lex_luthor.position());
+    assert_eq!(
+      Ok(Token::Identifier(
+        "abc".to_owned(),
+        SourceSpan::new(1, 1, 1, 3)
+      )),
+      lex_luthor.next_token()
+    );
+
+    lex_luthor.reset("x_y + z".to_owned());
+
+    assert_eq!(0, lex_luthor.position());
+    assert_eq!(
+      Ok(Token::Identifier(
+        "x_y".to_owned(),
+        SourceSpan::new(1, 1, 1, 3)
+      )),
+      lex_luthor.next_token()
+    );
+  }
+
+  #[test]
+  fn the_first_character_on_a_line_is_in_column_1() {
+    let test_cases = vec![
+      ("+", SourceSpan::new(1, 1, 1, 1)),
+      ("\n+", SourceSpan::new(2, 1, 2, 1)),
+      ("\n\n+", SourceSpan::new(3, 1, 3, 1)),
+      ("\n  +", SourceSpan::new(2, 3, 2, 3)),
+      ("\r\n\r\n+", SourceSpan::new(3, 1, 3, 1)),
+      ("\r\r+", SourceSpan::new(3, 1, 3, 1)),
+      ("# comment\n+", SourceSpan::new(2, 1, 2, 1)),
+      ("/* a\n*/\n+", SourceSpan::new(3, 1, 3, 1)),
+    ];
+
+    for (input, expected) in test_cases {
+      let tokens = LexLuthor::new(input.to_owned()).lex().unwrap();
+
+      assert_eq!(Token::Plus(expected), tokens[0], "{:?}", input);
+    }
+  }
+
+  #[test]
+  fn eof_after_a_trailing_line_break_starts_a_new_line() {
+    let test_cases = vec![
+      ("+", SourceSpan::new(1, 2, 1, 2)),
+      ("+\n", SourceSpan::new(2, 1, 2, 1)),
+      ("+\r\n", SourceSpan::new(2, 1, 2, 1)),
+      ("+\r", SourceSpan::new(2, 1, 2, 1)),
+      ("+\n\n", SourceSpan::new(3, 1, 3, 1)),
+      ("+ # comment\n", SourceSpan::new(2, 1, 2, 1)),
+      ("\n", SourceSpan::new(2, 1, 2, 1)),
+    ];
+
+    for (input, expected) in test_cases {
+      let tokens = LexLuthor::new(input.to_owned()).lex().unwrap();
+
+      assert_eq!(Some(&Token::Eof(expected)), tokens.last(), "{:?}", input);
+    }
+  }
+
+  #[test]
+  fn stops_after_too_many_errors() {
+    let errors = LexLuthor::new("?".repeat(1000))
+      .with_max_errors(10)
+      .lex()
+      .unwrap_err();
+
+    assert_eq!(11, errors.len());
+    assert_eq!(
+      LexLuthorError::TooManyErrors {
+        source_span: SourceSpan::new(1, 10, 1, 10),
+        message: "too many errors, stopped after 10".to_owned(),
+      },
+      errors[10]
+    );
+
+    assert_eq!(
+      1000,
+      LexLuthor::new("?".repeat(1000)).lex().unwrap_err().len()
+    );
+
+    let errors = LexLuthor::new("? + ?".to_owned())
+      .with_max_errors(10)
+      .lex()
+      .unwrap_err();
+
+    assert_eq!(2, errors.len());
+  }
+
+  #[test]
+  fn lex_results_can_be_named_by_their_alias() {
+    fn operators(result: LexResult) -> Tokens {
+      result
+        .unwrap_or_default()
+        .into_iter()
+        .filter(Token::is_operator)
+        .collect()
+    }
+
+    assert_eq!(
+      vec![Token::Plus(SourceSpan::new(1, 3, 1, 3))],
+      operators(tokenize("x + 1"))
+    );
+    assert_eq!(Tokens::new(), operators(tokenize("x ? 1")));
+  }
+
+  #[test]
+  fn tells_shifts_and_comparisons_apart() {
+    let test_cases = vec![
+      ("<<", Token::LessLess(SourceSpan::new(1, 1, 1, 2))),
+      (">>", Token::GreaterGreater(SourceSpan::new(1, 1, 1, 2))),
+      ("<=", Token::LessThanOrEqual(SourceSpan::new(1, 1, 1, 2))),
+      (">=", Token::GreaterThanOrEqual(SourceSpan::new(1, 1, 1, 2))),
+      ("<", Token::LessThan(SourceSpan::new(1, 1, 1, 1))),
+      (">", Token::GreaterThan(SourceSpan::new(1, 1, 1, 1))),
+    ];
+
+    for (input, expected) in test_cases {
+      assert_eq!(
+        Ok(vec![
+          expected.clone(),
+          Token::Eof(SourceSpan::new(1, input.len() + 1, 1, input.len() + 1)),
+        ]),
+        LexLuthor::new(input.to_owned()).lex()
+      );
+    }
+
+    let kinds: Vec<TokenKind> = LexLuthor::new("<<= >>> <><".to_owned())
+      .lex()
+      .unwrap()
+      .iter()
+      .map(Token::kind)
+      .collect();
+
+    assert_eq!(
+      vec![
+        TokenKind::LessLess,
+        TokenKind::Equal,
+        TokenKind::GreaterGreater,
+        TokenKind::GreaterThan,
+        TokenKind::LessThan,
+        TokenKind::GreaterThan,
+        TokenKind::LessThan,
+        TokenKind::Eof,
+      ],
+      kinds
+    );
+  }
+
+  #[test]
+  fn lex_each_hands_over_what_lex_returns() {
+    for input in &["program x := 1 end", "+ ? - $", ""] {
+      let mut tokens = Vec::new();
+      let mut errors = Vec::new();
+
+      LexLuthor::new((*input).to_owned()).lex_each(|result| match result {
+        Ok(token) => tokens.push(token),
+        Err(error) => errors.push(error),
+      });
+
+      let expected = LexLuthor::new((*input).to_owned()).lex();
+
+      if errors.is_empty() {
+        assert_eq!(expected, Ok(tokens));
+      } else {
+        assert_eq!(expected, Err(errors));
+      }
+    }
+
+    let mut calls = 0;
+
+    LexLuthor::new("???".to_owned())
+      .with_max_errors(2)
+      .lex_each(|_| calls += 1);
+
+    assert_eq!(4, calls);
+  }
+
+  #[test]
+  fn only_reports_too_many_errors_when_input_is_left() {
+    let (tokens, errors) = LexLuthor::new("? ?".to_owned())
+      .with_max_errors(2)
+      .lex_with_recovery();
+
+    assert_eq!(vec![Token::Eof(SourceSpan::new(1, 4, 1, 4))], tokens);
+    assert_eq!(2, errors.len());
+    assert!(errors
+      .iter()
+      .all(|error| matches!(error, LexLuthorError::UnexpectedCharacter { .. })));
+  }
+
+  #[test]
+  fn still_ends_with_eof_after_too_many_errors() {
+    let (tokens, errors) = LexLuthor::new("? ? ? +".to_owned())
+      .with_max_errors(2)
+      .lex_with_recovery();
+
+    assert_eq!(vec![Token::Eof(SourceSpan::new(1, 4, 1, 4))], tokens);
+    assert_eq!(Ok(()), assert_well_formed(&tokens));
+    assert_eq!(3, errors.len());
+
+    assert_eq!(
+      Some(&LexEvent::Token(Token::Eof(SourceSpan::new(1, 4, 1, 4)))),
+      LexLuthor::new("? ? ? +".to_owned())
+        .with_max_errors(2)
+        .lex_events()
+        .last()
+    );
+  }
+
+  #[test]
+  fn errors_have_stable_codes() {
+    let last_error = |mut lex_luthor: LexLuthor| lex_luthor.lex().unwrap_err().pop().unwrap();
+
+    let test_cases = vec![
+      (LexLuthor::new("?".to_owned()), "E0001"),
+      (LexLuthor::new("x2".to_owned()), "E0002"),
+      (LexLuthor::new("2x".to_owned()), "E0003"),
+      (LexLuthor::new("/*".to_owned()), "E0004"),
+      (LexLuthor::new("\"".to_owned()), "E0005"),
+      (LexLuthor::new("\"\\q\"".to_owned()), "E0006"),
+      (LexLuthor::new("''".to_owned()), "E0007"),
       (
-        ">=",
-        vec![
-          Token::GreaterThanOrEqual(SourceSpan { line: 1, column: 2 }),
-          Token::Eof,
-        ],
+        LexLuthor::new("abc".to_owned()).with_max_identifier_length(2),
+        "E0008",
       ),
+      (LexLuthor::new("??".to_owned()).with_max_errors(1), "E0010"),
+    ];
+
+    for (lex_luthor, code) in test_cases {
+      assert_eq!(code, last_error(lex_luthor).code());
+    }
+  }
+
+  #[test]
+  fn lexes_the_token_at_an_offset() {
+    let mut lex_luthor = LexLuthor::new("+ -".to_owned());
+
+    assert_eq!(
+      Ok(Token::Minus(SourceSpan::new(1, 3, 1, 3))),
+      lex_luthor.lex_token_at(2)
+    );
+
+    let source_code = "program\r\n  café := 12345\n\tx ? y";
+    let mut lex_luthor = LexLuthor::new(source_code.to_owned()).with_tab_width(4);
+    let at = |text: &str| source_code.find(text).unwrap();
+
+    assert_eq!(
+      Ok(Token::Integer(12345, SourceSpan::new(2, 11, 2, 15))),
+      lex_luthor.lex_token_at(at("345"))
+    );
+    // The middle of the é, and whitespace before a token.
+    assert_eq!(
+      Ok(Token::Identifier(
+        "café".to_owned(),
+        SourceSpan::new(2, 3, 2, 6)
+      )),
+      lex_luthor.lex_token_at(at("é") + 1)
+    );
+    assert_eq!(
+      Ok(Token::ColonEqual(SourceSpan::new(2, 8, 2, 9))),
+      lex_luthor.lex_token_at(at(" :="))
+    );
+    assert_eq!(
+      Ok(Token::Program(SourceSpan::new(1, 1, 1, 7))),
+      lex_luthor.lex_token_at(0)
+    );
+    assert_eq!(
+      Err(LexLuthorError::UnexpectedCharacter {
+        source_span: SourceSpan::new(3, 7, 3, 7),
+        message: "unexpected character ?".to_owned(),
+      }),
+      lex_luthor.lex_token_at(at("?"))
+    );
+
+    // Lexing carries on after the token.
+    assert_eq!(
+      Ok(Token::Identifier(
+        "y".to_owned(),
+        SourceSpan::new(3, 9, 3, 9)
+      )),
+      lex_luthor.next_token()
+    );
+    assert_eq!(
+      Ok(Token::Eof(SourceSpan::new(3, 10, 3, 10))),
+      lex_luthor.lex_token_at(1000)
+    );
+    assert_eq!(Some(at("x")), lex_luthor.byte_offset(3, 5));
+
+    let mut lex_luthor = LexLuthor::new("\u{FEFF}+".to_owned());
+
+    assert_eq!(
+      Ok(Token::Plus(SourceSpan::new(1, 1, 1, 1))),
+      lex_luthor.lex_token_at(0)
+    );
+  }
+
+  #[test]
+  fn keeps_byte_ranges_of_later_lines_after_lexing_a_token_at_an_offset() {
+    let source_code = "x\r\ny\rz\nw";
+    let mut lex_luthor = LexLuthor::new(source_code.to_owned());
+
+    let tokens = lex_luthor.lex().unwrap();
+
+    assert_eq!(
+      Ok(Token::Identifier(
+        "x".to_owned(),
+        SourceSpan::new(1, 1, 1, 1)
+      )),
+      lex_luthor.lex_token_at(0)
+    );
+
+    for (token, text) in tokens.iter().zip(&["x", "y", "z", "w"]) {
+      let start = source_code.find(text).unwrap();
+
+      assert_eq!(Some(start..start + 1), lex_luthor.byte_range(&token.span()));
+    }
+
+    assert_eq!(
+      Ok(Token::Identifier(
+        "z".to_owned(),
+        SourceSpan::new(3, 1, 3, 1)
+      )),
+      lex_luthor.lex_token_at(source_code.find('z').unwrap())
+    );
+    assert_eq!(
+      Ok(Token::Identifier(
+        "w".to_owned(),
+        SourceSpan::new(4, 1, 4, 1)
+      )),
+      lex_luthor.next_token()
+    );
+    assert_eq!(Some(7..8), lex_luthor.byte_range(&tokens[3].span()));
+  }
+
+  #[test]
+  fn lists_identifiers_up_to_the_first_error() {
+    assert_eq!(
+      vec![
+        ("x".to_owned(), SourceSpan::new(1, 1, 1, 1)),
+        ("y".to_owned(), SourceSpan::new(1, 5, 1, 5)),
+        ("zed".to_owned(), SourceSpan::new(1, 9, 1, 11)),
+      ],
+      LexLuthor::new("x + y * zed".to_owned()).identifiers()
+    );
+    assert_eq!(
+      vec![("x".to_owned(), SourceSpan::new(2, 3, 2, 3))],
+      LexLuthor::new("program\n  x := y2 + z".to_owned()).identifiers()
+    );
+  }
+
+  #[test]
+  fn tokenize_matches_lex() {
+    assert_eq!(LexLuthor::new("+ -".to_owned()).lex(), tokenize("+ -"));
+    assert_eq!(LexLuthor::new("?".to_owned()).lex(), tokenize("?"));
+  }
+
+  #[test]
+  fn exposes_its_progress_through_the_source_code() {
+    let mut lex_luthor = LexLuthor::new("<=\n  +".to_owned());
+
+    assert_eq!(
+      (1, 1, 0),
       (
-        "&",
-        vec![
-          Token::Ampersand(SourceSpan { line: 1, column: 1 }),
-          Token::Eof,
-        ],
-      ),
+        lex_luthor.line(),
+        lex_luthor.column(),
+        lex_luthor.position()
+      )
+    );
+
+    lex_luthor.next_token().unwrap();
+    assert_eq!(
+      (1, 3, 2),
       (
-        "|",
-        vec![Token::Pipe(SourceSpan { line: 1, column: 1 }), Token::Eof],
-      ),
+        lex_luthor.line(),
+        lex_luthor.column(),
+        lex_luthor.position()
+      )
+    );
+
+    lex_luthor.next_token().unwrap();
+    assert_eq!(
+      (2, 4, 6),
       (
-        "(",
-        vec![
-          Token::LeftParen(SourceSpan { line: 1, column: 1 }),
-          Token::Eof,
-        ],
-      ),
+        lex_luthor.line(),
+        lex_luthor.column(),
+        lex_luthor.position()
+      )
+    );
+
+    lex_luthor.next_token().unwrap();
+    assert_eq!(
+      (2, 4, 6),
       (
-        ")",
-        vec![
-          Token::RightParen(SourceSpan { line: 1, column: 1 }),
-          Token::Eof,
-        ],
-      ),
-      ("", vec![Token::Eof]),
-    ];
+        lex_luthor.line(),
+        lex_luthor.column(),
+        lex_luthor.position()
+      )
+    );
+  }
 
-    for (input, expected_output) in test_cases {
-      let actual = LexLuthor::new(input.to_owned()).lex();
+  #[test]
+  fn recovers_the_tokens_around_errors() {
+    let (tokens, errors) = LexLuthor::new("+ ? -".to_owned()).lex_with_recovery();
 
-      assert_eq!(Ok(expected_output), actual);
-    }
+    assert_eq!(
+      vec![
+        Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+        Token::Minus(SourceSpan::new(1, 5, 1, 5)),
+        Token::Eof(SourceSpan::new(1, 6, 1, 6)),
+      ],
+      tokens
+    );
+    assert_eq!(
+      vec![LexLuthorError::UnexpectedCharacter {
+        source_span: SourceSpan::new(1, 3, 1, 3),
+        message: "unexpected character ?".to_owned(),
+      }],
+      errors
+    );
   }
 
   #[test]
-  fn errors_on_unknown_tokens() {
-    let test_cases = vec![
-      (
-        "?",
-        vec![LexLuthorError::UnexpectedCharacter {
-          source_span: SourceSpan { line: 1, column: 1 },
-          message: "unexpected character ?".to_owned(),
-        }],
-      ),
+  fn lexes_from_readers() {
+    let source_code = "program\n  x = 0x1F # comment";
+
+    let from_reader = LexLuthor::from_reader(std::io::Cursor::new(source_code.as_bytes()))
+      .unwrap()
+      .lex();
+
+    assert_eq!(LexLuthor::new(source_code.to_owned()).lex(), from_reader);
+  }
+
+  #[test]
+  fn rejects_readers_that_are_not_utf8() {
+    let error = LexLuthor::from_reader(&[0x2b, 0xff][..]).unwrap_err();
+
+    assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+  }
+
+  #[test]
+  fn interns_identifiers_when_asked_to() {
+    let mut lex_luthor = LexLuthor::new("x + y * x if".to_owned()).with_interner();
+
+    let tokens = lex_luthor.lex().unwrap();
+
+    let first_x = lex_luthor.symbol(&tokens[0]).unwrap();
+    let second_x = lex_luthor.symbol(&tokens[4]).unwrap();
+    let y = lex_luthor.symbol(&tokens[2]).unwrap();
+    let interner = lex_luthor.interner().unwrap();
+
+    assert_eq!(first_x, second_x);
+    assert_ne!(first_x, y);
+    assert_eq!(2, interner.len());
+    assert_eq!(None, lex_luthor.symbol(&tokens[5]));
+
+    // The tokens themselves share the interned name, not just the interner's copy.
+    match (&tokens[0], &tokens[4], &tokens[2]) {
       (
-        "+-=/    ?",
-        vec![LexLuthorError::UnexpectedCharacter {
-          source_span: SourceSpan { line: 1, column: 9 },
+        Token::InternedIdentifier(first_x, first_x_source_span),
+        Token::InternedIdentifier(second_x, second_x_source_span),
+        Token::InternedIdentifier(y, _),
+      ) => {
+        assert!(Rc::ptr_eq(first_x, second_x));
+        assert!(!Rc::ptr_eq(first_x, y));
+        assert_eq!(SourceSpan::new(1, 1, 1, 1), *first_x_source_span);
+        assert_eq!(SourceSpan::new(1, 9, 1, 9), *second_x_source_span);
+      }
+      tokens => panic!("expected interned identifiers, got {:?}", tokens),
+    }
+  }
+
+  #[test]
+  fn does_not_intern_by_default() {
+    let mut lex_luthor = LexLuthor::new("x".to_owned());
+
+    let tokens = lex_luthor.lex().unwrap();
+
+    assert!(lex_luthor.interner().is_none());
+    assert_eq!(None, lex_luthor.symbol(&tokens[0]));
+  }
+
+  #[test]
+  fn lex_events_keep_tokens_and_errors_in_source_order() {
+    let events = LexLuthor::new("+ ? -".to_owned()).lex_events();
+
+    assert_eq!(
+      vec![
+        LexEvent::Token(Token::Plus(SourceSpan::new(1, 1, 1, 1))),
+        LexEvent::Error(LexLuthorError::UnexpectedCharacter {
+          source_span: SourceSpan::new(1, 3, 1, 3),
           message: "unexpected character ?".to_owned(),
-        }],
-      ),
-    ];
+        }),
+        LexEvent::Token(Token::Minus(SourceSpan::new(1, 5, 1, 5))),
+        LexEvent::Token(Token::Eof(SourceSpan::new(1, 6, 1, 6))),
+      ],
+      events
+    );
+  }
 
-    for (input, expected_error) in test_cases {
-      let actual = LexLuthor::new(input.to_owned()).lex();
+  #[test]
+  fn can_be_reset_to_lex_new_source_code() {
+    let mut lex_luthor = LexLuthor::new("x\n+".to_owned()).with_case_insensitive_keywords(true);
 
-      assert_eq!(Err(expected_error), actual);
-    }
+    assert_eq!(tokenize("x\n+"), lex_luthor.lex());
+
+    lex_luthor.reset("IF y".to_owned());
+
+    assert_eq!(
+      Ok(vec![
+        Token::If(SourceSpan::new(1, 1, 1, 2)),
+        Token::Identifier("y".to_owned(), SourceSpan::new(1, 4, 1, 4)),
+        Token::Eof(SourceSpan::new(1, 5, 1, 5)),
+      ]),
+      lex_luthor.lex()
+    );
+
+    lex_luthor.reset("- *".to_owned());
+    lex_luthor.peek_token_n(1).unwrap();
+    lex_luthor.reset("/".to_owned());
+
+    assert_eq!(
+      tokenize("/").unwrap(),
+      lex_luthor.map(Result::unwrap).collect::<Vec<_>>()
+    );
   }
 
   #[test]
-  fn recognizes_lines_and_columns() {
+  fn eof_is_just_past_the_last_character() {
     let test_cases = vec![
-      (
-        "+",
-        Ok(vec![
-          Token::Plus(SourceSpan { line: 1, column: 1 }),
-          Token::Eof,
-        ]),
-      ),
-      (
-        "\n+",
-        Ok(vec![
-          Token::Plus(SourceSpan { line: 2, column: 1 }),
-          Token::Eof,
-        ]),
-      ),
-      (
-        "+\n-",
-        Ok(vec![
-          Token::Plus(SourceSpan { line: 1, column: 1 }),
-          Token::Minus(SourceSpan { line: 2, column: 1 }),
-          Token::Eof,
-        ]),
-      ),
-      (
-        "\n\n\n     !",
-        Ok(vec![
-          Token::Bang(SourceSpan { line: 4, column: 6 }),
-          Token::Eof,
-        ]),
-      ),
+      ("", SourceSpan::new(1, 1, 1, 1)),
+      ("+", SourceSpan::new(1, 2, 1, 2)),
+      ("+  ", SourceSpan::new(1, 4, 1, 4)),
+      ("+\n", SourceSpan::new(2, 1, 2, 1)),
+      ("+\n--", SourceSpan::new(2, 3, 2, 3)),
     ];
 
     for (input, expected) in test_cases {
-      let actual = LexLuthor::new(input.to_owned()).lex();
+      let tokens = LexLuthor::new(input.to_owned()).lex().unwrap();
 
-      assert_eq!(expected, actual);
+      assert_eq!(Some(&Token::Eof(expected)), tokens.last());
     }
   }
 
   #[test]
-  fn keywords() {
+  fn keyword_prefixes_are_identifiers() {
     let test_cases = vec![
-      (
-        "program",
-        vec![
-          Token::Program(SourceSpan { line: 1, column: 7 }),
-          Token::Eof,
-        ],
-      ),
-      (
-        "define",
-        vec![Token::Define(SourceSpan { line: 1, column: 6 }), Token::Eof],
-      ),
-      (
-        "not",
-        vec![Token::Not(SourceSpan { line: 1, column: 3 }), Token::Eof],
-      ),
-      (
-        "variable",
-        vec![
-          Token::Variable(SourceSpan { line: 1, column: 8 }),
-          Token::Eof,
-        ],
-      ),
-      (
-        "is",
-        vec![Token::Is(SourceSpan { line: 1, column: 2 }), Token::Eof],
-      ),
-      (
-        "natural",
-        vec![
-          Token::Natural(SourceSpan { line: 1, column: 7 }),
-          Token::Eof,
-        ],
-      ),
-      (
-        "real",
-        vec![Token::Real(SourceSpan { line: 1, column: 4 }), Token::Eof],
-      ),
-      (
-        "char",
-        vec![Token::Char(SourceSpan { line: 1, column: 4 }), Token::Eof],
-      ),
-      (
-        "boolean",
-        vec![
-          Token::Boolean(SourceSpan { line: 1, column: 7 }),
-          Token::Eof,
-        ],
-      ),
-      (
-        "execute",
-        vec![
-          Token::Execute(SourceSpan { line: 1, column: 7 }),
-          Token::Eof,
-        ],
-      ),
-      (
-        "set",
-        vec![Token::Set(SourceSpan { line: 1, column: 3 }), Token::Eof],
-      ),
-      (
-        "get",
-        vec![Token::Get(SourceSpan { line: 1, column: 3 }), Token::Eof],
-      ),
-      (
-        "to",
-        vec![Token::To(SourceSpan { line: 1, column: 2 }), Token::Eof],
-      ),
-      (
-        "put",
-        vec![Token::Put(SourceSpan { line: 1, column: 3 }), Token::Eof],
-      ),
-      (
-        "loop",
-        vec![Token::Loop(SourceSpan { line: 1, column: 4 }), Token::Eof],
-      ),
-      (
-        "while",
-        vec![Token::While(SourceSpan { line: 1, column: 5 }), Token::Eof],
-      ),
-      (
-        "do",
-        vec![Token::Do(SourceSpan { line: 1, column: 2 }), Token::Eof],
-      ),
-      (
-        "true",
-        vec![Token::True(SourceSpan { line: 1, column: 4 }), Token::Eof],
-      ),
-      (
-        "false",
-        vec![Token::False(SourceSpan { line: 1, column: 5 }), Token::Eof],
-      ),
+      "iffy",
+      "thenceforth",
+      "elsewhere",
+      "endless",
+      "ending",
+      "xoring",
+      "constants",
+      "returns",
+      "functional",
     ];
 
-    for (input, expected) in test_cases {
+    for input in test_cases {
       let actual = LexLuthor::new(input.to_owned()).lex();
 
-      assert_eq!(Ok(expected), actual);
+      assert_eq!(
+        Ok(vec![
+          Token::Identifier(input.to_owned(), SourceSpan::new(1, 1, 1, input.len())),
+          Token::Eof(SourceSpan::new(1, input.len() + 1, 1, input.len() + 1)),
+        ]),
+        actual
+      );
     }
   }
 
   #[test]
-  fn identifiers() {
-    let test_cases = vec![
-      (
-        "x",
-        Ok(vec![
-          Token::Identifier("x".to_owned(), SourceSpan { line: 1, column: 1 }),
-          Token::Eof,
-        ]),
-      ),
-      (
-        "_x",
-        Ok(vec![
-          Token::Identifier("_x".to_owned(), SourceSpan { line: 1, column: 2 }),
-          Token::Eof,
-        ]),
-      ),
-      (
-        "_",
-        Ok(vec![
-          Token::Identifier("_".to_owned(), SourceSpan { line: 1, column: 1 }),
-          Token::Eof,
-        ]),
-      ),
-      (
-        "x__",
-        Err(vec![LexLuthorError::InvalidIdentifier {
-          source_span: SourceSpan { line: 1, column: 3 },
-          message: "x__ is not a valid identifier, _ must be followed by a letter".to_owned(),
-        }]),
-      ),
-      (
-        "x2",
-        Err(vec![LexLuthorError::InvalidIdentifier {
-          source_span: SourceSpan { line: 1, column: 2 },
-          message: "x2 is not a valid identifier, 2 must be followed by a letter".to_owned(),
-        }]),
-      ),
-      (
-        "x2y_z2w",
-        Ok(vec![
-          Token::Identifier("x2y_z2w".to_owned(), SourceSpan { line: 1, column: 7 }),
-          Token::Eof,
-        ]),
-      ),
-      (
-        "__",
-        Err(vec![LexLuthorError::InvalidIdentifier {
-          source_span: SourceSpan { line: 1, column: 2 },
-          message: "__ is not a valid identifier, _ must be followed by a letter".to_owned(),
-        }]),
-      ),
-      (
-        "__variable_name",
-        Err(vec![LexLuthorError::InvalidIdentifier {
-          source_span: SourceSpan {
-            line: 1,
-            column: 15,
-          },
-          message: "__variable_name is not a valid identifier, _ must be followed by a letter"
-            .to_owned(),
-        }]),
-      ),
-    ];
+  fn peeking_does_not_consume_tokens() {
+    let input = "+ ? ** x";
 
-    for (input, expected) in test_cases {
-      let actual = LexLuthor::new(input.to_owned()).lex();
+    let expected: Vec<Result<Token, LexLuthorError>> = LexLuthor::new(input.to_owned()).collect();
 
-      assert_eq!(expected, actual);
+    let mut lex_luthor = LexLuthor::new(input.to_owned());
+    let mut actual = Vec::new();
+
+    for index in 0..expected.len() {
+      assert_eq!(expected[index].as_ref(), lex_luthor.peek_token());
+      assert_eq!(expected[index].as_ref(), lex_luthor.peek_token());
+
+      if index + 2 < expected.len() {
+        assert_eq!(expected[index + 2].as_ref(), lex_luthor.peek_token_n(2));
+      }
+
+      actual.push(lex_luthor.next_token());
     }
+
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn peeking_past_the_end_returns_eof() {
+    let mut lex_luthor = LexLuthor::new("+".to_owned());
+
+    assert_eq!(
+      Ok(&Token::Eof(SourceSpan::new(1, 2, 1, 2))),
+      lex_luthor.peek_token_n(5)
+    );
+    assert_eq!(
+      Ok(Token::Plus(SourceSpan::new(1, 1, 1, 1))),
+      lex_luthor.next_token()
+    );
+    assert_eq!(
+      Ok(Token::Eof(SourceSpan::new(1, 2, 1, 2))),
+      lex_luthor.next_token()
+    );
+  }
+
+  #[test]
+  fn renders_diagnostics_with_the_offending_line() {
+    let mut lex_luthor = LexLuthor::new("+\n+ ? -\n-".to_owned());
+
+    let errors = lex_luthor.lex().unwrap_err();
+
+    assert_eq!(
+      "error: unexpected character ?\n --> 2:3\n  |\n2 | + ? -\n  |   ^",
+      lex_luthor.render_diagnostic(&errors[0])
+    );
+  }
+
+  #[test]
+  fn renders_diagnostics_on_lines_ended_by_a_lone_carriage_return() {
+    let mut lex_luthor = LexLuthor::new("+\r?\r\n-".to_owned());
+
+    let errors = lex_luthor.lex().unwrap_err();
+
+    assert_eq!(
+      "error: unexpected character ?\n --> 2:1\n  |\n2 | ?\n  | ^",
+      lex_luthor.render_diagnostic(&errors[0])
+    );
+  }
+
+  #[test]
+  fn renders_reversed_spans_with_a_single_caret() {
+    let lex_luthor = LexLuthor::new("abc".to_owned());
+
+    assert_eq!(
+      "error: backwards\n --> 1:3\n  |\n1 | abc\n  |   ^",
+      lex_luthor.render_diagnostic_at(SourceSpan::new(1, 3, 1, 1), "backwards")
+    );
+  }
+
+  #[test]
+  fn renders_diagnostics_for_snippets() {
+    let mut lex_luthor = LexLuthor::new_at("x ?\n  ? y".to_owned(), 10, 5);
+
+    let errors = lex_luthor.lex().unwrap_err();
+
+    assert_eq!(
+      "error: unexpected character ?\n  --> 10:7\n   |\n10 | x ?\n   |   ^",
+      lex_luthor.render_diagnostic(&errors[0])
+    );
+    assert_eq!(
+      "error: unexpected character ?\n  --> 11:3\n   |\n11 |   ? y\n   |   ^",
+      lex_luthor.render_diagnostic(&errors[1])
+    );
+  }
+
+  #[test]
+  fn renders_diagnostics_under_the_whole_span() {
+    let input = format!("{}\t99999999999999999999", "\n".repeat(9));
+    let mut lex_luthor = LexLuthor::new(input);
+
+    let errors = lex_luthor.lex().unwrap_err();
+
+    assert_eq!(
+      "error: 99999999999999999999 is not a valid integer, integers must be at most 9223372036854775807\n  --> 10:2\n   |\n10 | \t99999999999999999999\n   | \t^^^^^^^^^^^^^^^^^^^^",
+      lex_luthor.render_diagnostic(&errors[0])
+    );
   }
 }
@@ -1,4 +1,8 @@
+pub mod eval;
+pub mod interner;
 pub mod lex_luthor;
+pub mod operator;
+pub mod parser;
 pub mod source_code;
 pub mod token;
 
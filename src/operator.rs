@@ -0,0 +1,151 @@
+use crate::token::{Token, TokenKind};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOperator {
+  Plus,
+  Negate,
+  Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOperator {
+  Add,
+  Subtract,
+  Multiply,
+  Divide,
+  IntegerDivide,
+  Power,
+  Remainder,
+  Modulo,
+  Equal,
+  NotEqual,
+  LessThan,
+  GreaterThan,
+  LessThanOrEqual,
+  GreaterThanOrEqual,
+  And,
+  Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Associativity {
+  Left,
+  Right,
+}
+
+// Binary operators from the loosest to the tightest binding. & and && are both and,
+// | and || are both or, the same way not and ! are both negation.
+#[rustfmt::skip]
+pub const BINARY_OPERATORS: &[(TokenKind, BinaryOperator, u8, Associativity)] = &[
+  (TokenKind::Pipe,               BinaryOperator::Or,                 1, Associativity::Left),
+  (TokenKind::PipePipe,           BinaryOperator::Or,                 1, Associativity::Left),
+  (TokenKind::Ampersand,          BinaryOperator::And,                2, Associativity::Left),
+  (TokenKind::AmpersandAmpersand, BinaryOperator::And,                2, Associativity::Left),
+  (TokenKind::EqualEqual,         BinaryOperator::Equal,              3, Associativity::Left),
+  (TokenKind::NotEqual,           BinaryOperator::NotEqual,           3, Associativity::Left),
+  (TokenKind::LessThan,           BinaryOperator::LessThan,           3, Associativity::Left),
+  (TokenKind::GreaterThan,        BinaryOperator::GreaterThan,        3, Associativity::Left),
+  (TokenKind::LessThanOrEqual,    BinaryOperator::LessThanOrEqual,    3, Associativity::Left),
+  (TokenKind::GreaterThanOrEqual, BinaryOperator::GreaterThanOrEqual, 3, Associativity::Left),
+  (TokenKind::Plus,               BinaryOperator::Add,                4, Associativity::Left),
+  (TokenKind::Minus,              BinaryOperator::Subtract,           4, Associativity::Left),
+  (TokenKind::Star,               BinaryOperator::Multiply,           5, Associativity::Left),
+  (TokenKind::Slash,              BinaryOperator::Divide,             5, Associativity::Left),
+  (TokenKind::SlashSlash,         BinaryOperator::IntegerDivide,      5, Associativity::Left),
+  (TokenKind::Percent,            BinaryOperator::Remainder,          5, Associativity::Left),
+  (TokenKind::PercentPercent,     BinaryOperator::Modulo,             5, Associativity::Left),
+  (TokenKind::StarStar,           BinaryOperator::Power,              7, Associativity::Right),
+];
+
+// Unary operators bind tighter than every binary operator except **, so -2 ** 2 is
+// -(2 ** 2) while -2 * 3 is (-2) * 3.
+pub const UNARY_PRECEDENCE: u8 = 6;
+
+pub fn binary_operator(kind: TokenKind) -> Option<(BinaryOperator, u8, Associativity)> {
+  BINARY_OPERATORS
+    .iter()
+    .find(|(operator_kind, ..)| *operator_kind == kind)
+    .map(|(_, operator, precedence, associativity)| (*operator, *precedence, *associativity))
+}
+
+pub fn precedence(operator: BinaryOperator) -> u8 {
+  BINARY_OPERATORS
+    .iter()
+    .find(|(_, binary_operator, ..)| *binary_operator == operator)
+    .map(|(_, _, precedence, _)| *precedence)
+    .expect("every binary operator is in the table")
+}
+
+pub fn unary_operator(token: &Token) -> Option<UnaryOperator> {
+  match token {
+    Token::Plus(_) => Some(UnaryOperator::Plus),
+    Token::Minus(_) => Some(UnaryOperator::Negate),
+    Token::Not(_) | Token::Bang(_) => Some(UnaryOperator::Not),
+    _ => None,
+  }
+}
+
+impl fmt::Display for UnaryOperator {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      UnaryOperator::Plus => write!(f, "+"),
+      UnaryOperator::Negate => write!(f, "-"),
+      UnaryOperator::Not => write!(f, "not "),
+    }
+  }
+}
+
+impl fmt::Display for BinaryOperator {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let operator = match self {
+      BinaryOperator::Add => "+",
+      BinaryOperator::Subtract => "-",
+      BinaryOperator::Multiply => "*",
+      BinaryOperator::Divide => "/",
+      BinaryOperator::IntegerDivide => "//",
+      BinaryOperator::Power => "**",
+      BinaryOperator::Remainder => "%",
+      BinaryOperator::Modulo => "%%",
+      BinaryOperator::Equal => "==",
+      BinaryOperator::NotEqual => "!=",
+      BinaryOperator::LessThan => "<",
+      BinaryOperator::GreaterThan => ">",
+      BinaryOperator::LessThanOrEqual => "<=",
+      BinaryOperator::GreaterThanOrEqual => ">=",
+      BinaryOperator::And => "&",
+      BinaryOperator::Or => "|",
+    };
+
+    write!(f, "{}", operator)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn precedence_table_orders_operator_groups() {
+    let groups = [
+      BinaryOperator::Or,
+      BinaryOperator::And,
+      BinaryOperator::LessThan,
+      BinaryOperator::Add,
+      BinaryOperator::Multiply,
+      BinaryOperator::Power,
+    ];
+
+    for pair in groups.windows(2) {
+      assert!(precedence(pair[0]) < precedence(pair[1]), "{:?}", pair);
+    }
+
+    assert!(precedence(BinaryOperator::Multiply) < UNARY_PRECEDENCE);
+    assert!(UNARY_PRECEDENCE < precedence(BinaryOperator::Power));
+    assert_eq!(
+      Some((BinaryOperator::Power, 7, Associativity::Right)),
+      binary_operator(TokenKind::StarStar)
+    );
+    assert_eq!(None, binary_operator(TokenKind::LeftParen));
+  }
+}
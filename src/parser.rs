@@ -0,0 +1,602 @@
+use crate::operator::{
+  binary_operator, unary_operator, Associativity, BinaryOperator, UnaryOperator, UNARY_PRECEDENCE,
+};
+use crate::source_code::SourceSpan;
+use crate::token::{Token, TokenKind};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+  Integer(i64, SourceSpan),
+  Real(f64, SourceSpan),
+  Char(char, SourceSpan),
+  Boolean(bool, SourceSpan),
+  String(String, SourceSpan),
+  Identifier(String, SourceSpan),
+  Unary {
+    operator: UnaryOperator,
+    operator_source_span: SourceSpan,
+    operand: Box<Expr>,
+  },
+  Binary {
+    operator: BinaryOperator,
+    operator_source_span: SourceSpan,
+    left: Box<Expr>,
+    right: Box<Expr>,
+  },
+}
+
+impl Expr {
+  pub fn source_span(&self) -> SourceSpan {
+    match self {
+      Expr::Integer(_, source_span)
+      | Expr::Real(_, source_span)
+      | Expr::Char(_, source_span)
+      | Expr::Boolean(_, source_span)
+      | Expr::String(_, source_span)
+      | Expr::Identifier(_, source_span) => *source_span,
+      Expr::Unary {
+        operator_source_span,
+        operand,
+        ..
+      } => operator_source_span.through(&operand.source_span()),
+      Expr::Binary { left, right, .. } => left.source_span().merge(&right.source_span()),
+    }
+  }
+}
+
+// Every operation is wrapped in parentheses so the shape of the tree is visible.
+impl fmt::Display for Expr {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Expr::Integer(value, _) => write!(f, "{}", value),
+      Expr::Real(value, _) if value.fract() == 0.0 => write!(f, "{:.1}", value),
+      Expr::Real(value, _) => write!(f, "{}", value),
+      Expr::Char(character, _) => write!(f, "{:?}", character),
+      Expr::Boolean(value, _) => write!(f, "{}", value),
+      Expr::String(string, _) => write!(f, "{:?}", string),
+      Expr::Identifier(identifier, _) => write!(f, "{}", identifier),
+      Expr::Unary {
+        operator, operand, ..
+      } => write!(f, "({}{})", operator, operand),
+      Expr::Binary {
+        operator,
+        left,
+        right,
+        ..
+      } => write!(f, "({} {} {})", left, operator, right),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParseError {
+  UnexpectedToken {
+    found: Token,
+    expected: String,
+    source_span: SourceSpan,
+  },
+  UnexpectedEof {
+    expected: String,
+    source_span: SourceSpan,
+  },
+}
+
+impl ParseError {
+  pub fn source_span(&self) -> SourceSpan {
+    match self {
+      ParseError::UnexpectedToken { source_span, .. }
+      | ParseError::UnexpectedEof { source_span, .. } => *source_span,
+    }
+  }
+
+  pub fn message(&self) -> String {
+    match self {
+      ParseError::UnexpectedToken {
+        found, expected, ..
+      } => format!("expected {}, found {}", expected, found),
+      ParseError::UnexpectedEof { expected, .. } => {
+        format!("expected {}, found the end of input", expected)
+      }
+    }
+  }
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let source_span = self.source_span();
+
+    write!(
+      f,
+      "line {}, column {}: {}",
+      source_span.line,
+      source_span.column,
+      self.message()
+    )
+  }
+}
+
+impl std::error::Error for ParseError {}
+
+// Walks over lexed tokens for a parser. Comments are dropped and the tokens always end
+// with Eof, which is returned again on every read past the end.
+#[derive(Debug)]
+pub struct TokenCursor {
+  tokens: Vec<Token>,
+  position: usize,
+}
+
+impl TokenCursor {
+  pub fn new(tokens: Vec<Token>) -> TokenCursor {
+    let mut tokens: Vec<Token> = tokens
+      .into_iter()
+      .filter(|token| !matches!(token, Token::Comment(..)))
+      .collect();
+
+    // The parser always has a token to look at, even when given a stream that
+    // doesn't end with Eof.
+    if !matches!(tokens.last(), Some(Token::Eof(_))) {
+      let source_span = match tokens.last() {
+        None => SourceSpan::new(1, 1, 1, 1),
+        Some(token) => {
+          let source_span = token.span();
+
+          SourceSpan::new(
+            source_span.end_line,
+            source_span.end_column + 1,
+            source_span.end_line,
+            source_span.end_column + 1,
+          )
+        }
+      };
+
+      tokens.push(Token::Eof(source_span));
+    }
+
+    TokenCursor {
+      tokens,
+      position: 0,
+    }
+  }
+
+  pub fn peek(&self) -> &Token {
+    &self.tokens[self.position]
+  }
+
+  // Never moves past Eof.
+  pub fn advance(&mut self) -> Token {
+    let token = self.tokens[self.position].clone();
+
+    if self.position < self.tokens.len() - 1 {
+      self.position += 1;
+    }
+
+    token
+  }
+
+  // Consumes the next token if it is of the given kind.
+  pub fn expect(&mut self, kind: TokenKind) -> Result<Token, ParseError> {
+    if self.peek().kind() == kind {
+      Ok(self.advance())
+    } else {
+      Err(self.unexpected_token(&describe(kind)))
+    }
+  }
+
+  pub fn unexpected_token(&self, expected: &str) -> ParseError {
+    match self.peek() {
+      Token::Eof(source_span) => ParseError::UnexpectedEof {
+        expected: expected.to_owned(),
+        source_span: *source_span,
+      },
+      token => ParseError::UnexpectedToken {
+        found: token.clone(),
+        expected: expected.to_owned(),
+        source_span: token.span(),
+      },
+    }
+  }
+}
+
+// How a kind of token is named in "expected ..." messages.
+fn describe(kind: TokenKind) -> String {
+  match kind {
+    TokenKind::Identifier => "an identifier".to_owned(),
+    TokenKind::Integer => "an integer".to_owned(),
+    TokenKind::RealLiteral => "a real number".to_owned(),
+    TokenKind::String => "a string".to_owned(),
+    TokenKind::CharLiteral => "a character".to_owned(),
+    TokenKind::Comment => "a comment".to_owned(),
+    TokenKind::Keyword => "a keyword".to_owned(),
+    kind => Token::dummy(kind).to_string(),
+  }
+}
+
+pub struct Parser {
+  cursor: TokenCursor,
+}
+
+impl Parser {
+  pub fn new(tokens: Vec<Token>) -> Parser {
+    Parser {
+      cursor: TokenCursor::new(tokens),
+    }
+  }
+
+  pub fn parse_expression(&mut self) -> Result<Expr, ParseError> {
+    self.parse_expression_with_precedence(0)
+  }
+
+  // Keeps folding binary operators into the left operand for as long as they bind at
+  // least as tight as `min_precedence`.
+  fn parse_expression_with_precedence(&mut self, min_precedence: u8) -> Result<Expr, ParseError> {
+    let mut left = self.parse_unary()?;
+
+    while let Some((operator, precedence, associativity)) =
+      binary_operator(self.cursor.peek().kind())
+    {
+      if precedence < min_precedence {
+        break;
+      }
+
+      let operator_source_span = self.cursor.advance().span();
+
+      let right = match associativity {
+        Associativity::Left => self.parse_expression_with_precedence(precedence + 1)?,
+        Associativity::Right => self.parse_expression_with_precedence(precedence)?,
+      };
+
+      left = Expr::Binary {
+        operator,
+        operator_source_span,
+        left: Box::new(left),
+        right: Box::new(right),
+      };
+    }
+
+    Ok(left)
+  }
+
+  fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+    match unary_operator(self.cursor.peek()) {
+      None => self.parse_primary(),
+      Some(operator) => {
+        let operator_source_span = self.cursor.advance().span();
+        let operand = self.parse_expression_with_precedence(UNARY_PRECEDENCE)?;
+
+        Ok(Expr::Unary {
+          operator,
+          operator_source_span,
+          operand: Box::new(operand),
+        })
+      }
+    }
+  }
+
+  fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+    let expr = match self.cursor.peek() {
+      Token::Integer(value, source_span) => Expr::Integer(*value, *source_span),
+      Token::RealLiteral(value, source_span) => Expr::Real(*value, *source_span),
+      Token::CharLiteral(character, source_span) => Expr::Char(*character, *source_span),
+      Token::String(string, source_span) => Expr::String(string.clone(), *source_span),
+      Token::True(source_span) => Expr::Boolean(true, *source_span),
+      Token::False(source_span) => Expr::Boolean(false, *source_span),
+      Token::Identifier(identifier, source_span) => {
+        Expr::Identifier(identifier.clone(), *source_span)
+      }
+      Token::InternedIdentifier(identifier, source_span) => {
+        Expr::Identifier(identifier.to_string(), *source_span)
+      }
+      Token::LeftParen(_) => {
+        self.cursor.advance();
+
+        let expr = self.parse_expression()?;
+
+        self.cursor.expect(TokenKind::RightParen)?;
+
+        return Ok(expr);
+      }
+      _ => return Err(self.cursor.unexpected_token("an expression")),
+    };
+
+    self.cursor.advance();
+
+    Ok(expr)
+  }
+
+  fn expect_eof(&self) -> Result<(), ParseError> {
+    match self.cursor.peek() {
+      Token::Eof(_) => Ok(()),
+      _ => Err(self.cursor.unexpected_token("the end of the expression")),
+    }
+  }
+}
+
+pub fn parse_expression(tokens: Vec<Token>) -> Result<Expr, ParseError> {
+  let mut parser = Parser::new(tokens);
+
+  let expr = parser.parse_expression()?;
+
+  parser.expect_eof()?;
+
+  Ok(expr)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lex_luthor::{tokenize, LexLuthor};
+
+  fn parse(input: &str) -> Result<Expr, ParseError> {
+    parse_expression(tokenize(input).unwrap())
+  }
+
+  #[test]
+  fn multiplication_binds_tighter_than_addition() {
+    let expr = parse("1 + 2 * 3").unwrap();
+
+    assert_eq!(
+      Expr::Binary {
+        operator: BinaryOperator::Add,
+        operator_source_span: SourceSpan::new(1, 3, 1, 3),
+        left: Box::new(Expr::Integer(1, SourceSpan::new(1, 1, 1, 1))),
+        right: Box::new(Expr::Binary {
+          operator: BinaryOperator::Multiply,
+          operator_source_span: SourceSpan::new(1, 7, 1, 7),
+          left: Box::new(Expr::Integer(2, SourceSpan::new(1, 5, 1, 5))),
+          right: Box::new(Expr::Integer(3, SourceSpan::new(1, 9, 1, 9))),
+        }),
+      },
+      expr
+    );
+    assert_eq!(SourceSpan::new(1, 1, 1, 9), expr.source_span());
+  }
+
+  #[test]
+  fn respects_precedence_and_parentheses() {
+    let test_cases = vec![
+      ("1 * 2 + 3", "((1 * 2) + 3)"),
+      ("(1 + 2) * 3", "((1 + 2) * 3)"),
+      ("1 - 2 - 3", "((1 - 2) - 3)"),
+      ("2 ** 3 ** 2", "(2 ** (3 ** 2))"),
+      ("-2 ** 2", "(-(2 ** 2))"),
+      ("2 ** -1", "(2 ** (-1))"),
+      ("1 + 2 < 3 * 4", "((1 + 2) < (3 * 4))"),
+      ("a // b % c %% d", "(((a // b) % c) %% d)"),
+      ("not x == !y", "((not x) == (not y))"),
+      ("((2.5))", "2.5"),
+      ("'c' != \"s\"", "('c' != \"s\")"),
+      ("true <= false", "(true <= false)"),
+      ("-2 * 3", "((-2) * 3)"),
+      ("+1 - +2", "((+1) - (+2))"),
+      ("a | b & c == d", "(a | (b & (c == d)))"),
+      ("a && b || c", "((a & b) | c)"),
+      ("1 < 2 + 3 ** 2 * 4", "(1 < (2 + ((3 ** 2) * 4)))"),
+    ];
+
+    for (input, expected) in test_cases {
+      assert_eq!(expected, parse(input).unwrap().to_string(), "{}", input);
+    }
+  }
+
+  #[test]
+  fn parses_interned_identifiers() {
+    let tokens = LexLuthor::new("a + a".to_owned())
+      .with_interner()
+      .lex()
+      .unwrap();
+
+    assert_eq!("(a + a)", parse_expression(tokens).unwrap().to_string());
+  }
+
+  #[test]
+  fn power_is_right_associative() {
+    assert_eq!(
+      Expr::Binary {
+        operator: BinaryOperator::Power,
+        operator_source_span: SourceSpan::new(1, 3, 1, 4),
+        left: Box::new(Expr::Integer(2, SourceSpan::new(1, 1, 1, 1))),
+        right: Box::new(Expr::Binary {
+          operator: BinaryOperator::Power,
+          operator_source_span: SourceSpan::new(1, 8, 1, 9),
+          left: Box::new(Expr::Integer(3, SourceSpan::new(1, 6, 1, 6))),
+          right: Box::new(Expr::Integer(2, SourceSpan::new(1, 11, 1, 11))),
+        }),
+      },
+      parse("2 ** 3 ** 2").unwrap()
+    );
+  }
+
+  #[test]
+  fn subtraction_is_left_associative() {
+    assert_eq!(
+      Expr::Binary {
+        operator: BinaryOperator::Subtract,
+        operator_source_span: SourceSpan::new(1, 7, 1, 7),
+        left: Box::new(Expr::Binary {
+          operator: BinaryOperator::Subtract,
+          operator_source_span: SourceSpan::new(1, 3, 1, 3),
+          left: Box::new(Expr::Integer(1, SourceSpan::new(1, 1, 1, 1))),
+          right: Box::new(Expr::Integer(2, SourceSpan::new(1, 5, 1, 5))),
+        }),
+        right: Box::new(Expr::Integer(3, SourceSpan::new(1, 9, 1, 9))),
+      },
+      parse("1 - 2 - 3").unwrap()
+    );
+  }
+
+  #[test]
+  fn errors_on_mismatched_parentheses() {
+    let test_cases = vec![
+      (
+        "(1 + 2",
+        ParseError::UnexpectedEof {
+          expected: ")".to_owned(),
+          source_span: SourceSpan::new(1, 7, 1, 7),
+        },
+      ),
+      (
+        "1 + 2)",
+        ParseError::UnexpectedToken {
+          found: Token::RightParen(SourceSpan::new(1, 6, 1, 6)),
+          expected: "the end of the expression".to_owned(),
+          source_span: SourceSpan::new(1, 6, 1, 6),
+        },
+      ),
+      (
+        "(1 + )",
+        ParseError::UnexpectedToken {
+          found: Token::RightParen(SourceSpan::new(1, 6, 1, 6)),
+          expected: "an expression".to_owned(),
+          source_span: SourceSpan::new(1, 6, 1, 6),
+        },
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      assert_eq!(Err(expected), parse(input));
+    }
+  }
+
+  #[test]
+  fn cursors_expect_tokens_of_a_kind() {
+    let plus = Token::Plus(SourceSpan::new(1, 1, 1, 1));
+    let eof = Token::Eof(SourceSpan::new(1, 2, 1, 2));
+
+    let mut cursor = TokenCursor::new(vec![plus.clone(), eof.clone()]);
+
+    assert_eq!(Ok(plus), cursor.expect(TokenKind::Plus));
+    assert_eq!(Ok(eof.clone()), cursor.expect(TokenKind::Eof));
+
+    let mut cursor = TokenCursor::new(vec![Token::Minus(SourceSpan::new(1, 1, 1, 1)), eof]);
+
+    assert_eq!(
+      Err(ParseError::UnexpectedToken {
+        found: Token::Minus(SourceSpan::new(1, 1, 1, 1)),
+        expected: "+".to_owned(),
+        source_span: SourceSpan::new(1, 1, 1, 1),
+      }),
+      cursor.expect(TokenKind::Plus)
+    );
+    assert_eq!(
+      Err(ParseError::UnexpectedToken {
+        found: Token::Minus(SourceSpan::new(1, 1, 1, 1)),
+        expected: "an identifier".to_owned(),
+        source_span: SourceSpan::new(1, 1, 1, 1),
+      }),
+      cursor.expect(TokenKind::Identifier)
+    );
+  }
+
+  #[test]
+  fn cursors_keep_returning_eof_past_the_end() {
+    let mut cursor = TokenCursor::new(vec![Token::Integer(1, SourceSpan::new(2, 3, 2, 3))]);
+
+    assert_eq!(
+      Token::Integer(1, SourceSpan::new(2, 3, 2, 3)),
+      cursor.advance()
+    );
+
+    for _ in 0..3 {
+      assert_eq!(Token::Eof(SourceSpan::new(2, 4, 2, 4)), cursor.advance());
+    }
+
+    assert_eq!(
+      Err(ParseError::UnexpectedEof {
+        expected: "+".to_owned(),
+        source_span: SourceSpan::new(2, 4, 2, 4),
+      }),
+      cursor.expect(TokenKind::Plus)
+    );
+  }
+
+  #[test]
+  fn errors_on_malformed_token_streams() {
+    let test_cases = vec![
+      (
+        "1 +",
+        ParseError::UnexpectedEof {
+          expected: "an expression".to_owned(),
+          source_span: SourceSpan::new(1, 4, 1, 4),
+        },
+      ),
+      (
+        "1 2",
+        ParseError::UnexpectedToken {
+          found: Token::Integer(2, SourceSpan::new(1, 3, 1, 3)),
+          expected: "the end of the expression".to_owned(),
+          source_span: SourceSpan::new(1, 3, 1, 3),
+        },
+      ),
+      (
+        "* 2",
+        ParseError::UnexpectedToken {
+          found: Token::Star(SourceSpan::new(1, 1, 1, 1)),
+          expected: "an expression".to_owned(),
+          source_span: SourceSpan::new(1, 1, 1, 1),
+        },
+      ),
+      (
+        "(1 if",
+        ParseError::UnexpectedToken {
+          found: Token::If(SourceSpan::new(1, 4, 1, 5)),
+          expected: ")".to_owned(),
+          source_span: SourceSpan::new(1, 4, 1, 5),
+        },
+      ),
+    ];
+
+    for (input, expected) in test_cases {
+      assert_eq!(Err(expected), parse(input));
+    }
+  }
+
+  #[test]
+  fn renders_parse_errors_like_lexer_errors() {
+    let lex_luthor = LexLuthor::new("1 +\n  (2 if 3)".to_owned());
+    let error = parse("1 +\n  (2 if 3)").unwrap_err();
+
+    assert_eq!(
+      "error: expected ), found if\n --> 2:6\n  |\n2 |   (2 if 3)\n  |      ^^",
+      lex_luthor.render_diagnostic_at(error.source_span(), &error.message())
+    );
+  }
+
+  #[test]
+  fn errors_display_their_position_and_message() {
+    assert_eq!(
+      "line 1, column 4: expected ), found if",
+      parse("(1 if").unwrap_err().to_string()
+    );
+    assert_eq!(
+      "line 1, column 4: expected an expression, found the end of input",
+      parse("1 +").unwrap_err().to_string()
+    );
+
+    let error: Box<dyn std::error::Error> = Box::new(parse("1 +").unwrap_err());
+
+    assert_eq!(
+      "line 1, column 4: expected an expression, found the end of input",
+      error.to_string()
+    );
+  }
+
+  #[test]
+  fn ignores_comments_and_a_missing_eof() {
+    let mut tokens = LexLuthor::new("1 /* one */ + 2".to_owned())
+      .preserving_comments(true)
+      .lex()
+      .unwrap();
+
+    tokens.pop();
+
+    assert_eq!("(1 + 2)", parse_expression(tokens).unwrap().to_string());
+    assert_eq!(
+      Err(ParseError::UnexpectedEof {
+        expected: "an expression".to_owned(),
+        source_span: SourceSpan::new(1, 1, 1, 1),
+      }),
+      parse_expression(vec![])
+    );
+  }
+}
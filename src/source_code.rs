@@ -1,5 +1,79 @@
-#[derive(Debug, PartialEq)]
+// Spans are ordered by where they start and then by where they end, which relies on
+// the order of the fields below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SourceSpan {
   pub line: usize,
   pub column: usize,
+  pub end_line: usize,
+  pub end_column: usize,
+}
+
+impl SourceSpan {
+  pub fn new(line: usize, column: usize, end_line: usize, end_column: usize) -> SourceSpan {
+    SourceSpan {
+      line,
+      column,
+      end_line,
+      end_column,
+    }
+  }
+
+  // From the start of this span to the end of `end`.
+  pub fn through(&self, end: &SourceSpan) -> SourceSpan {
+    SourceSpan::new(self.line, self.column, end.end_line, end.end_column)
+  }
+
+  // The smallest span covering both, whichever comes first.
+  pub fn merge(&self, other: &SourceSpan) -> SourceSpan {
+    let (line, column) = (self.line, self.column).min((other.line, other.column));
+    let (end_line, end_column) =
+      (self.end_line, self.end_column).max((other.end_line, other.end_column));
+
+    SourceSpan::new(line, column, end_line, end_column)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn merging_covers_both_spans() {
+    let first = SourceSpan::new(1, 5, 1, 8);
+    let second = SourceSpan::new(3, 2, 3, 4);
+
+    assert_eq!(SourceSpan::new(1, 5, 3, 4), first.merge(&second));
+    assert_eq!(SourceSpan::new(1, 5, 3, 4), second.merge(&first));
+    assert_eq!(
+      SourceSpan::new(1, 1, 2, 9),
+      SourceSpan::new(1, 1, 2, 9).merge(&SourceSpan::new(1, 4, 1, 6))
+    );
+  }
+
+  #[test]
+  fn spans_are_ordered_by_line_then_column() {
+    let mut spans = vec![
+      SourceSpan::new(2, 1, 2, 1),
+      SourceSpan::new(1, 10, 1, 12),
+      SourceSpan::new(3, 4, 3, 4),
+      SourceSpan::new(1, 2, 1, 2),
+      SourceSpan::new(2, 7, 2, 9),
+      SourceSpan::new(1, 2, 2, 1),
+    ];
+
+    spans.sort();
+
+    assert_eq!(
+      vec![
+        SourceSpan::new(1, 2, 1, 2),
+        SourceSpan::new(1, 2, 2, 1),
+        SourceSpan::new(1, 10, 1, 12),
+        SourceSpan::new(2, 1, 2, 1),
+        SourceSpan::new(2, 7, 2, 9),
+        SourceSpan::new(3, 4, 3, 4),
+      ],
+      spans
+    );
+  }
 }
@@ -1,41 +1,74 @@
+use crate::operator;
 use crate::source_code::SourceSpan;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::rc::Rc;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Token {
   LeftBrace(SourceSpan),
   RightBrace(SourceSpan),
   LeftBracket(SourceSpan),
   RightBracket(SourceSpan),
   Comma(SourceSpan),
+  Colon(SourceSpan),
+  ColonEqual(SourceSpan),
+  Semicolon(SourceSpan),
+  Newline(SourceSpan),
+  Dot(SourceSpan),
+  DotDot(SourceSpan),
   Plus(SourceSpan),
   Minus(SourceSpan),
   Star(SourceSpan),
   Slash(SourceSpan),
+  SlashSlash(SourceSpan),
   StarStar(SourceSpan),
   Percent(SourceSpan),
   PercentPercent(SourceSpan),
   Equal(SourceSpan),
+  EqualEqual(SourceSpan),
   NotEqual(SourceSpan),
   LessThan(SourceSpan),
   GreaterThan(SourceSpan),
   LessThanOrEqual(SourceSpan),
   GreaterThanOrEqual(SourceSpan),
+  LessLess(SourceSpan),
+  GreaterGreater(SourceSpan),
+  Caret(SourceSpan),
   Ampersand(SourceSpan),
+  AmpersandAmpersand(SourceSpan),
   Pipe(SourceSpan),
+  PipePipe(SourceSpan),
   Bang(SourceSpan),
+  Question(SourceSpan),
   LeftParen(SourceSpan),
   RightParen(SourceSpan),
   Program(SourceSpan),
   Identifier(String, SourceSpan),
+  // An identifier lexed with an interner, sharing its name with every other
+  // occurrence of it.
+  InternedIdentifier(Rc<str>, SourceSpan),
+  Integer(i64, SourceSpan),
+  RealLiteral(f64, SourceSpan),
+  String(String, SourceSpan),
+  CharLiteral(char, SourceSpan),
+  Comment(String, SourceSpan),
+  // A keyword the lexer was configured with on top of the built in ones.
+  Keyword(String, SourceSpan),
   Define(SourceSpan),
   Not(SourceSpan),
   Variable(SourceSpan),
+  Constant(SourceSpan),
   Is(SourceSpan),
   Natural(SourceSpan),
   Real(SourceSpan),
   Char(SourceSpan),
   Boolean(SourceSpan),
   Execute(SourceSpan),
+  Function(SourceSpan),
+  Return(SourceSpan),
   Set(SourceSpan),
   Get(SourceSpan),
   To(SourceSpan),
@@ -43,32 +76,1138 @@ pub enum Token {
   Loop(SourceSpan),
   While(SourceSpan),
   Do(SourceSpan),
+  If(SourceSpan),
+  Then(SourceSpan),
+  Else(SourceSpan),
+  End(SourceSpan),
   True(SourceSpan),
   False(SourceSpan),
+  Xor(SourceSpan),
+  Eof(SourceSpan),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TokenKind {
+  LeftBrace,
+  RightBrace,
+  LeftBracket,
+  RightBracket,
+  Comma,
+  Colon,
+  ColonEqual,
+  Semicolon,
+  Newline,
+  Dot,
+  DotDot,
+  Plus,
+  Minus,
+  Star,
+  Slash,
+  SlashSlash,
+  StarStar,
+  Percent,
+  PercentPercent,
+  Equal,
+  EqualEqual,
+  NotEqual,
+  LessThan,
+  GreaterThan,
+  LessThanOrEqual,
+  GreaterThanOrEqual,
+  LessLess,
+  GreaterGreater,
+  Caret,
+  Ampersand,
+  AmpersandAmpersand,
+  Pipe,
+  PipePipe,
+  Bang,
+  Question,
+  LeftParen,
+  RightParen,
+  Program,
+  Identifier,
+  Integer,
+  RealLiteral,
+  String,
+  CharLiteral,
+  Comment,
+  Keyword,
+  Define,
+  Not,
+  Variable,
+  Constant,
+  Is,
+  Natural,
+  Real,
+  Char,
+  Boolean,
+  Execute,
+  Function,
+  Return,
+  Set,
+  Get,
+  To,
+  Put,
+  Loop,
+  While,
+  Do,
+  If,
+  Then,
+  Else,
+  End,
+  True,
+  False,
+  Xor,
   Eof,
 }
 
+impl TokenKind {
+  pub fn is_keyword(&self) -> bool {
+    matches!(
+      self,
+      TokenKind::Program
+        | TokenKind::Define
+        | TokenKind::Not
+        | TokenKind::Variable
+        | TokenKind::Constant
+        | TokenKind::Is
+        | TokenKind::Natural
+        | TokenKind::Real
+        | TokenKind::Char
+        | TokenKind::Boolean
+        | TokenKind::Execute
+        | TokenKind::Function
+        | TokenKind::Return
+        | TokenKind::Set
+        | TokenKind::Get
+        | TokenKind::To
+        | TokenKind::Put
+        | TokenKind::Loop
+        | TokenKind::While
+        | TokenKind::Do
+        | TokenKind::If
+        | TokenKind::Then
+        | TokenKind::Else
+        | TokenKind::End
+        | TokenKind::True
+        | TokenKind::False
+        | TokenKind::Xor
+        | TokenKind::Keyword
+    )
+  }
+
+  pub fn is_operator(&self) -> bool {
+    matches!(
+      self,
+      TokenKind::Plus
+        | TokenKind::Minus
+        | TokenKind::Star
+        | TokenKind::Slash
+        | TokenKind::SlashSlash
+        | TokenKind::StarStar
+        | TokenKind::Percent
+        | TokenKind::PercentPercent
+        | TokenKind::Equal
+        | TokenKind::EqualEqual
+        | TokenKind::NotEqual
+        | TokenKind::LessThan
+        | TokenKind::GreaterThan
+        | TokenKind::LessThanOrEqual
+        | TokenKind::GreaterThanOrEqual
+        | TokenKind::LessLess
+        | TokenKind::GreaterGreater
+        | TokenKind::Caret
+        | TokenKind::Ampersand
+        | TokenKind::AmpersandAmpersand
+        | TokenKind::Pipe
+        | TokenKind::PipePipe
+        | TokenKind::Bang
+        | TokenKind::ColonEqual
+        | TokenKind::DotDot
+    )
+  }
+
+  // true and false are keywords as well as literals.
+  pub fn is_literal(&self) -> bool {
+    matches!(
+      self,
+      TokenKind::Integer
+        | TokenKind::RealLiteral
+        | TokenKind::String
+        | TokenKind::CharLiteral
+        | TokenKind::True
+        | TokenKind::False
+    )
+  }
+}
+
+impl Token {
+  pub fn kind(&self) -> TokenKind {
+    match self {
+      Token::LeftBrace(_) => TokenKind::LeftBrace,
+      Token::RightBrace(_) => TokenKind::RightBrace,
+      Token::LeftBracket(_) => TokenKind::LeftBracket,
+      Token::RightBracket(_) => TokenKind::RightBracket,
+      Token::Comma(_) => TokenKind::Comma,
+      Token::Colon(_) => TokenKind::Colon,
+      Token::ColonEqual(_) => TokenKind::ColonEqual,
+      Token::Semicolon(_) => TokenKind::Semicolon,
+      Token::Newline(_) => TokenKind::Newline,
+      Token::Dot(_) => TokenKind::Dot,
+      Token::DotDot(_) => TokenKind::DotDot,
+      Token::Plus(_) => TokenKind::Plus,
+      Token::Minus(_) => TokenKind::Minus,
+      Token::Star(_) => TokenKind::Star,
+      Token::Slash(_) => TokenKind::Slash,
+      Token::SlashSlash(_) => TokenKind::SlashSlash,
+      Token::StarStar(_) => TokenKind::StarStar,
+      Token::Percent(_) => TokenKind::Percent,
+      Token::PercentPercent(_) => TokenKind::PercentPercent,
+      Token::Equal(_) => TokenKind::Equal,
+      Token::EqualEqual(_) => TokenKind::EqualEqual,
+      Token::NotEqual(_) => TokenKind::NotEqual,
+      Token::LessThan(_) => TokenKind::LessThan,
+      Token::GreaterThan(_) => TokenKind::GreaterThan,
+      Token::LessThanOrEqual(_) => TokenKind::LessThanOrEqual,
+      Token::GreaterThanOrEqual(_) => TokenKind::GreaterThanOrEqual,
+      Token::LessLess(_) => TokenKind::LessLess,
+      Token::GreaterGreater(_) => TokenKind::GreaterGreater,
+      Token::Caret(_) => TokenKind::Caret,
+      Token::Ampersand(_) => TokenKind::Ampersand,
+      Token::AmpersandAmpersand(_) => TokenKind::AmpersandAmpersand,
+      Token::Pipe(_) => TokenKind::Pipe,
+      Token::PipePipe(_) => TokenKind::PipePipe,
+      Token::Bang(_) => TokenKind::Bang,
+      Token::Question(_) => TokenKind::Question,
+      Token::LeftParen(_) => TokenKind::LeftParen,
+      Token::RightParen(_) => TokenKind::RightParen,
+      Token::Program(_) => TokenKind::Program,
+      Token::Identifier(..) | Token::InternedIdentifier(..) => TokenKind::Identifier,
+      Token::Integer(..) => TokenKind::Integer,
+      Token::RealLiteral(..) => TokenKind::RealLiteral,
+      Token::String(..) => TokenKind::String,
+      Token::CharLiteral(..) => TokenKind::CharLiteral,
+      Token::Comment(..) => TokenKind::Comment,
+      Token::Keyword(..) => TokenKind::Keyword,
+      Token::Define(_) => TokenKind::Define,
+      Token::Not(_) => TokenKind::Not,
+      Token::Variable(_) => TokenKind::Variable,
+      Token::Constant(_) => TokenKind::Constant,
+      Token::Is(_) => TokenKind::Is,
+      Token::Natural(_) => TokenKind::Natural,
+      Token::Real(_) => TokenKind::Real,
+      Token::Char(_) => TokenKind::Char,
+      Token::Boolean(_) => TokenKind::Boolean,
+      Token::Execute(_) => TokenKind::Execute,
+      Token::Function(_) => TokenKind::Function,
+      Token::Return(_) => TokenKind::Return,
+      Token::Set(_) => TokenKind::Set,
+      Token::Get(_) => TokenKind::Get,
+      Token::To(_) => TokenKind::To,
+      Token::Put(_) => TokenKind::Put,
+      Token::Loop(_) => TokenKind::Loop,
+      Token::While(_) => TokenKind::While,
+      Token::Do(_) => TokenKind::Do,
+      Token::If(_) => TokenKind::If,
+      Token::Then(_) => TokenKind::Then,
+      Token::Else(_) => TokenKind::Else,
+      Token::End(_) => TokenKind::End,
+      Token::True(_) => TokenKind::True,
+      Token::False(_) => TokenKind::False,
+      Token::Xor(_) => TokenKind::Xor,
+      Token::Eof(_) => TokenKind::Eof,
+    }
+  }
+
+  // A token of the given kind at line 0, column 0, which no lexed token can have. Payloads
+  // are empty or zero.
+  pub fn dummy(kind: TokenKind) -> Token {
+    let source_span = SourceSpan::new(0, 0, 0, 0);
+
+    match kind {
+      TokenKind::LeftBrace => Token::LeftBrace(source_span),
+      TokenKind::RightBrace => Token::RightBrace(source_span),
+      TokenKind::LeftBracket => Token::LeftBracket(source_span),
+      TokenKind::RightBracket => Token::RightBracket(source_span),
+      TokenKind::Comma => Token::Comma(source_span),
+      TokenKind::Colon => Token::Colon(source_span),
+      TokenKind::ColonEqual => Token::ColonEqual(source_span),
+      TokenKind::Semicolon => Token::Semicolon(source_span),
+      TokenKind::Newline => Token::Newline(source_span),
+      TokenKind::Dot => Token::Dot(source_span),
+      TokenKind::DotDot => Token::DotDot(source_span),
+      TokenKind::Plus => Token::Plus(source_span),
+      TokenKind::Minus => Token::Minus(source_span),
+      TokenKind::Star => Token::Star(source_span),
+      TokenKind::Slash => Token::Slash(source_span),
+      TokenKind::SlashSlash => Token::SlashSlash(source_span),
+      TokenKind::StarStar => Token::StarStar(source_span),
+      TokenKind::Percent => Token::Percent(source_span),
+      TokenKind::PercentPercent => Token::PercentPercent(source_span),
+      TokenKind::Equal => Token::Equal(source_span),
+      TokenKind::EqualEqual => Token::EqualEqual(source_span),
+      TokenKind::NotEqual => Token::NotEqual(source_span),
+      TokenKind::LessThan => Token::LessThan(source_span),
+      TokenKind::GreaterThan => Token::GreaterThan(source_span),
+      TokenKind::LessThanOrEqual => Token::LessThanOrEqual(source_span),
+      TokenKind::GreaterThanOrEqual => Token::GreaterThanOrEqual(source_span),
+      TokenKind::LessLess => Token::LessLess(source_span),
+      TokenKind::GreaterGreater => Token::GreaterGreater(source_span),
+      TokenKind::Caret => Token::Caret(source_span),
+      TokenKind::Ampersand => Token::Ampersand(source_span),
+      TokenKind::AmpersandAmpersand => Token::AmpersandAmpersand(source_span),
+      TokenKind::Pipe => Token::Pipe(source_span),
+      TokenKind::PipePipe => Token::PipePipe(source_span),
+      TokenKind::Bang => Token::Bang(source_span),
+      TokenKind::Question => Token::Question(source_span),
+      TokenKind::LeftParen => Token::LeftParen(source_span),
+      TokenKind::RightParen => Token::RightParen(source_span),
+      TokenKind::Program => Token::Program(source_span),
+      TokenKind::Identifier => Token::Identifier(String::new(), source_span),
+      TokenKind::Integer => Token::Integer(0, source_span),
+      TokenKind::RealLiteral => Token::RealLiteral(0.0, source_span),
+      TokenKind::String => Token::String(String::new(), source_span),
+      TokenKind::CharLiteral => Token::CharLiteral('\0', source_span),
+      TokenKind::Comment => Token::Comment(String::new(), source_span),
+      TokenKind::Keyword => Token::Keyword(String::new(), source_span),
+      TokenKind::Define => Token::Define(source_span),
+      TokenKind::Not => Token::Not(source_span),
+      TokenKind::Variable => Token::Variable(source_span),
+      TokenKind::Constant => Token::Constant(source_span),
+      TokenKind::Is => Token::Is(source_span),
+      TokenKind::Natural => Token::Natural(source_span),
+      TokenKind::Real => Token::Real(source_span),
+      TokenKind::Char => Token::Char(source_span),
+      TokenKind::Boolean => Token::Boolean(source_span),
+      TokenKind::Execute => Token::Execute(source_span),
+      TokenKind::Function => Token::Function(source_span),
+      TokenKind::Return => Token::Return(source_span),
+      TokenKind::Set => Token::Set(source_span),
+      TokenKind::Get => Token::Get(source_span),
+      TokenKind::To => Token::To(source_span),
+      TokenKind::Put => Token::Put(source_span),
+      TokenKind::Loop => Token::Loop(source_span),
+      TokenKind::While => Token::While(source_span),
+      TokenKind::Do => Token::Do(source_span),
+      TokenKind::If => Token::If(source_span),
+      TokenKind::Then => Token::Then(source_span),
+      TokenKind::Else => Token::Else(source_span),
+      TokenKind::End => Token::End(source_span),
+      TokenKind::True => Token::True(source_span),
+      TokenKind::False => Token::False(source_span),
+      TokenKind::Xor => Token::Xor(source_span),
+      TokenKind::Eof => Token::Eof(source_span),
+    }
+  }
+
+  pub fn is_eof(&self) -> bool {
+    matches!(self, Token::Eof(_))
+  }
+
+  pub fn is_keyword(&self) -> bool {
+    self.kind().is_keyword()
+  }
+
+  pub fn is_operator(&self) -> bool {
+    self.kind().is_operator()
+  }
+
+  pub fn is_literal(&self) -> bool {
+    self.kind().is_literal()
+  }
+
+  // Whether the token can start an expression as a unary operator, as - does in -1.
+  pub fn can_be_prefix(&self) -> bool {
+    operator::unary_operator(self).is_some()
+  }
+
+  // Whether the token can stand between two operands, as - does in 2 - 1.
+  pub fn can_be_infix(&self) -> bool {
+    self.precedence().is_some()
+  }
+
+  // How tightly the token binds as a binary operator, higher binds tighter. Looked up
+  // in the parser's operator table so there is only one place to change it.
+  pub fn precedence(&self) -> Option<u8> {
+    operator::binary_operator(self.kind()).map(|(_, precedence, _)| precedence)
+  }
+
+  // The reserved word a keyword token was lexed from, so errors can name it. Keywords
+  // lexed case insensitively give the lowercase spelling.
+  pub fn keyword_lexeme(&self) -> Option<&'static str> {
+    let kind = self.kind();
+
+    KEYWORDS
+      .iter()
+      .find(|(_, keyword)| keyword(self.span()).kind() == kind)
+      .map(|(lexeme, _)| *lexeme)
+  }
+
+  // not and ! are two spellings of the same operator, the lexer keeps them apart so
+  // tokens still display as they were written.
+  pub fn as_logical_negation(&self) -> bool {
+    matches!(self, Token::Not(_) | Token::Bang(_))
+  }
+
+  // The name of an identifier, interned or not.
+  pub fn identifier(&self) -> Option<&str> {
+    match self {
+      Token::Identifier(identifier, _) => Some(identifier),
+      Token::InternedIdentifier(identifier, _) => Some(identifier),
+      _ => None,
+    }
+  }
+
+  pub fn span(&self) -> SourceSpan {
+    match self {
+      Token::LeftBrace(source_span)
+      | Token::RightBrace(source_span)
+      | Token::LeftBracket(source_span)
+      | Token::RightBracket(source_span)
+      | Token::Comma(source_span)
+      | Token::Colon(source_span)
+      | Token::ColonEqual(source_span)
+      | Token::Semicolon(source_span)
+      | Token::Newline(source_span)
+      | Token::Dot(source_span)
+      | Token::DotDot(source_span)
+      | Token::Plus(source_span)
+      | Token::Minus(source_span)
+      | Token::Star(source_span)
+      | Token::Slash(source_span)
+      | Token::SlashSlash(source_span)
+      | Token::StarStar(source_span)
+      | Token::Percent(source_span)
+      | Token::PercentPercent(source_span)
+      | Token::Equal(source_span)
+      | Token::EqualEqual(source_span)
+      | Token::NotEqual(source_span)
+      | Token::LessThan(source_span)
+      | Token::GreaterThan(source_span)
+      | Token::LessThanOrEqual(source_span)
+      | Token::GreaterThanOrEqual(source_span)
+      | Token::LessLess(source_span)
+      | Token::GreaterGreater(source_span)
+      | Token::Caret(source_span)
+      | Token::Ampersand(source_span)
+      | Token::AmpersandAmpersand(source_span)
+      | Token::Pipe(source_span)
+      | Token::PipePipe(source_span)
+      | Token::Bang(source_span)
+      | Token::Question(source_span)
+      | Token::LeftParen(source_span)
+      | Token::RightParen(source_span)
+      | Token::Program(source_span)
+      | Token::Identifier(_, source_span)
+      | Token::InternedIdentifier(_, source_span)
+      | Token::Integer(_, source_span)
+      | Token::RealLiteral(_, source_span)
+      | Token::String(_, source_span)
+      | Token::CharLiteral(_, source_span)
+      | Token::Comment(_, source_span)
+      | Token::Keyword(_, source_span)
+      | Token::Define(source_span)
+      | Token::Not(source_span)
+      | Token::Variable(source_span)
+      | Token::Constant(source_span)
+      | Token::Is(source_span)
+      | Token::Natural(source_span)
+      | Token::Real(source_span)
+      | Token::Char(source_span)
+      | Token::Boolean(source_span)
+      | Token::Execute(source_span)
+      | Token::Function(source_span)
+      | Token::Return(source_span)
+      | Token::Set(source_span)
+      | Token::Get(source_span)
+      | Token::To(source_span)
+      | Token::Put(source_span)
+      | Token::Loop(source_span)
+      | Token::While(source_span)
+      | Token::Do(source_span)
+      | Token::If(source_span)
+      | Token::Then(source_span)
+      | Token::Else(source_span)
+      | Token::End(source_span)
+      | Token::True(source_span)
+      | Token::False(source_span)
+      | Token::Xor(source_span)
+      | Token::Eof(source_span) => *source_span,
+    }
+  }
+}
+
+// RealLiteral holds an f64, which is neither Eq nor Hash. The lexer never produces NaN,
+// so equality between tokens is still reflexive.
+impl Eq for Token {}
+
+impl Hash for Token {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    mem::discriminant(self).hash(state);
+
+    match self {
+      Token::LeftBrace(source_span)
+      | Token::RightBrace(source_span)
+      | Token::LeftBracket(source_span)
+      | Token::RightBracket(source_span)
+      | Token::Comma(source_span)
+      | Token::Colon(source_span)
+      | Token::ColonEqual(source_span)
+      | Token::Semicolon(source_span)
+      | Token::Newline(source_span)
+      | Token::Dot(source_span)
+      | Token::DotDot(source_span)
+      | Token::Plus(source_span)
+      | Token::Minus(source_span)
+      | Token::Star(source_span)
+      | Token::Slash(source_span)
+      | Token::SlashSlash(source_span)
+      | Token::StarStar(source_span)
+      | Token::Percent(source_span)
+      | Token::PercentPercent(source_span)
+      | Token::Equal(source_span)
+      | Token::EqualEqual(source_span)
+      | Token::NotEqual(source_span)
+      | Token::LessThan(source_span)
+      | Token::GreaterThan(source_span)
+      | Token::LessThanOrEqual(source_span)
+      | Token::GreaterThanOrEqual(source_span)
+      | Token::LessLess(source_span)
+      | Token::GreaterGreater(source_span)
+      | Token::Caret(source_span)
+      | Token::Ampersand(source_span)
+      | Token::AmpersandAmpersand(source_span)
+      | Token::Pipe(source_span)
+      | Token::PipePipe(source_span)
+      | Token::Bang(source_span)
+      | Token::Question(source_span)
+      | Token::LeftParen(source_span)
+      | Token::RightParen(source_span)
+      | Token::Program(source_span)
+      | Token::Define(source_span)
+      | Token::Not(source_span)
+      | Token::Variable(source_span)
+      | Token::Constant(source_span)
+      | Token::Is(source_span)
+      | Token::Natural(source_span)
+      | Token::Real(source_span)
+      | Token::Char(source_span)
+      | Token::Boolean(source_span)
+      | Token::Execute(source_span)
+      | Token::Function(source_span)
+      | Token::Return(source_span)
+      | Token::Set(source_span)
+      | Token::Get(source_span)
+      | Token::To(source_span)
+      | Token::Put(source_span)
+      | Token::Loop(source_span)
+      | Token::While(source_span)
+      | Token::Do(source_span)
+      | Token::If(source_span)
+      | Token::Then(source_span)
+      | Token::Else(source_span)
+      | Token::End(source_span)
+      | Token::True(source_span)
+      | Token::False(source_span)
+      | Token::Xor(source_span)
+      | Token::Eof(source_span) => source_span.hash(state),
+      Token::Identifier(identifier, source_span) => {
+        identifier.hash(state);
+        source_span.hash(state);
+      }
+      Token::InternedIdentifier(identifier, source_span) => {
+        identifier.hash(state);
+        source_span.hash(state);
+      }
+      Token::Integer(value, source_span) => {
+        value.hash(state);
+        source_span.hash(state);
+      }
+      Token::RealLiteral(value, source_span) => {
+        // 0.0 and -0.0 are equal, so they must hash the same.
+        let value = if *value == 0.0 { 0.0 } else { *value };
+        value.to_bits().hash(state);
+        source_span.hash(state);
+      }
+      Token::String(string, source_span) => {
+        string.hash(state);
+        source_span.hash(state);
+      }
+      Token::CharLiteral(character, source_span) => {
+        character.hash(state);
+        source_span.hash(state);
+      }
+      Token::Comment(comment, source_span) => {
+        comment.hash(state);
+        source_span.hash(state);
+      }
+      Token::Keyword(keyword, source_span) => {
+        keyword.hash(state);
+        source_span.hash(state);
+      }
+    }
+  }
+}
+
+type KeywordConstructor = fn(SourceSpan) -> Token;
+
+// Every reserved word and the token it lexes to.
+const KEYWORDS: &[(&str, KeywordConstructor)] = &[
+  ("program", Token::Program),
+  ("define", Token::Define),
+  ("not", Token::Not),
+  ("variable", Token::Variable),
+  ("constant", Token::Constant),
+  ("is", Token::Is),
+  ("natural", Token::Natural),
+  ("real", Token::Real),
+  ("char", Token::Char),
+  ("boolean", Token::Boolean),
+  ("execute", Token::Execute),
+  ("function", Token::Function),
+  ("return", Token::Return),
+  ("set", Token::Set),
+  ("get", Token::Get),
+  ("to", Token::To),
+  ("put", Token::Put),
+  ("loop", Token::Loop),
+  ("while", Token::While),
+  ("do", Token::Do),
+  ("if", Token::If),
+  ("then", Token::Then),
+  ("else", Token::Else),
+  ("end", Token::End),
+  ("true", Token::True),
+  ("false", Token::False),
+  ("xor", Token::Xor),
+];
+
+pub fn is_reserved_word(word: &str) -> bool {
+  KEYWORDS.iter().any(|(keyword, _)| *keyword == word)
+}
+
+pub fn keyword_token(word: &str, source_span: SourceSpan) -> Option<Token> {
+  KEYWORDS
+    .iter()
+    .find(|(keyword, _)| *keyword == word)
+    .map(|(_, token)| token(source_span))
+}
+
 pub fn token_from_identifier_or_keyword(lexeme: String, source_span: SourceSpan) -> Token {
-  match lexeme.to_lowercase().as_str() {
-    "program" => Token::Program(source_span),
-    "define" => Token::Define(source_span),
-    "not" => Token::Not(source_span),
-    "variable" => Token::Variable(source_span),
-    "is" => Token::Is(source_span),
-    "natural" => Token::Natural(source_span),
-    "real" => Token::Real(source_span),
-    "char" => Token::Char(source_span),
-    "boolean" => Token::Boolean(source_span),
-    "execute" => Token::Execute(source_span),
-    "set" => Token::Set(source_span),
-    "get" => Token::Get(source_span),
-    "to" => Token::To(source_span),
-    "put" => Token::Put(source_span),
-    "loop" => Token::Loop(source_span),
-    "while" => Token::While(source_span),
-    "do" => Token::Do(source_span),
-    "true" => Token::True(source_span),
-    "false" => Token::False(source_span),
-    _ => Token::Identifier(lexeme, source_span),
+  keyword_token(&lexeme, source_span).unwrap_or(Token::Identifier(lexeme, source_span))
+}
+
+// Checks what every lexed token stream guarantees, that there is exactly one Eof and
+// it comes last. Meant for catching lexer bugs in tests.
+pub fn assert_well_formed(tokens: &[Token]) -> Result<(), String> {
+  let eofs: Vec<usize> = tokens
+    .iter()
+    .enumerate()
+    .filter(|(_, token)| token.is_eof())
+    .map(|(index, _)| index)
+    .collect();
+
+  match eofs.as_slice() {
+    [] => Err("the tokens don't end with Eof".to_owned()),
+    [index] if *index == tokens.len() - 1 => Ok(()),
+    [index] => Err(format!(
+      "Eof at index {} is followed by {} more tokens",
+      index,
+      tokens.len() - 1 - index
+    )),
+    _ => Err(format!(
+      "expected exactly one Eof, found {} at indexes {:?}",
+      eofs.len(),
+      eofs
+    )),
+  }
+}
+
+// Writes tokens back out as source code, one space between each. Layout and the
+// spelling of numbers aren't kept, but lexing the result gives the same kinds of tokens.
+pub fn to_source(tokens: &[Token]) -> String {
+  let mut source_code = String::new();
+
+  for token in tokens {
+    match token {
+      Token::Eof(_) => break,
+      Token::Newline(_) => source_code.push('\n'),
+      // Anything after a line comment would become part of it.
+      Token::Comment(comment, _) if !comment.starts_with("/*") => {
+        source_code.push_str(comment);
+        source_code.push('\n');
+      }
+      token => {
+        source_code.push_str(&token.to_string());
+        source_code.push(' ');
+      }
+    }
+  }
+
+  source_code
+}
+
+// One row per token with its kind, lexeme and line:column, padded into columns. Meant
+// for reading, unlike to_source.
+pub fn pretty_print_tokens(tokens: &[Token]) -> String {
+  let rows: Vec<(String, String, String)> = tokens
+    .iter()
+    .map(|token| {
+      let source_span = token.span();
+      (
+        format!("{:?}", token.kind()),
+        // Block comments can span lines, which would break the table.
+        token.to_string().replace('\n', "\\n"),
+        format!("{}:{}", source_span.line, source_span.column),
+      )
+    })
+    .collect();
+
+  let kind_width = rows
+    .iter()
+    .map(|(kind, _, _)| kind.len())
+    .max()
+    .unwrap_or(0);
+  let lexeme_width = rows
+    .iter()
+    .map(|(_, lexeme, _)| lexeme.chars().count())
+    .max()
+    .unwrap_or(0);
+
+  let mut table = String::new();
+
+  for (kind, lexeme, position) in rows {
+    table.push_str(&format!(
+      "{:kind_width$}  {:lexeme_width$}  {}\n",
+      kind,
+      lexeme,
+      position,
+      kind_width = kind_width,
+      lexeme_width = lexeme_width
+    ));
+  }
+
+  table
+}
+
+fn write_escaped(f: &mut fmt::Formatter, text: &str, quote: char) -> fmt::Result {
+  write!(f, "{}", quote)?;
+
+  for character in text.chars() {
+    match character {
+      '\n' => write!(f, "\\n")?,
+      '\r' => write!(f, "\\r")?,
+      '\t' => write!(f, "\\t")?,
+      '\\' => write!(f, "\\\\")?,
+      character if character == quote => write!(f, "\\{}", character)?,
+      character => write!(f, "{}", character)?,
+    }
+  }
+
+  write!(f, "{}", quote)
+}
+
+impl fmt::Display for Token {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Token::LeftBrace(_) => write!(f, "{{"),
+      Token::RightBrace(_) => write!(f, "}}"),
+      Token::LeftBracket(_) => write!(f, "["),
+      Token::RightBracket(_) => write!(f, "]"),
+      Token::Comma(_) => write!(f, ","),
+      Token::Colon(_) => write!(f, ":"),
+      Token::ColonEqual(_) => write!(f, ":="),
+      Token::Semicolon(_) => write!(f, ";"),
+      Token::Newline(_) => write!(f, "<newline>"),
+      Token::Dot(_) => write!(f, "."),
+      Token::DotDot(_) => write!(f, ".."),
+      Token::Plus(_) => write!(f, "+"),
+      Token::Minus(_) => write!(f, "-"),
+      Token::Star(_) => write!(f, "*"),
+      Token::Slash(_) => write!(f, "/"),
+      Token::SlashSlash(_) => write!(f, "//"),
+      Token::StarStar(_) => write!(f, "**"),
+      Token::Percent(_) => write!(f, "%"),
+      Token::PercentPercent(_) => write!(f, "%%"),
+      Token::Equal(_) => write!(f, "="),
+      Token::EqualEqual(_) => write!(f, "=="),
+      Token::NotEqual(_) => write!(f, "!="),
+      Token::LessThan(_) => write!(f, "<"),
+      Token::GreaterThan(_) => write!(f, ">"),
+      Token::LessThanOrEqual(_) => write!(f, "<="),
+      Token::GreaterThanOrEqual(_) => write!(f, ">="),
+      Token::LessLess(_) => write!(f, "<<"),
+      Token::GreaterGreater(_) => write!(f, ">>"),
+      Token::Caret(_) => write!(f, "^"),
+      Token::Ampersand(_) => write!(f, "&"),
+      Token::AmpersandAmpersand(_) => write!(f, "&&"),
+      Token::Pipe(_) => write!(f, "|"),
+      Token::PipePipe(_) => write!(f, "||"),
+      Token::Bang(_) => write!(f, "!"),
+      Token::Question(_) => write!(f, "?"),
+      Token::LeftParen(_) => write!(f, "("),
+      Token::RightParen(_) => write!(f, ")"),
+      Token::Program(_) => write!(f, "program"),
+      Token::Identifier(identifier, _) => write!(f, "{}", identifier),
+      Token::InternedIdentifier(identifier, _) => write!(f, "{}", identifier),
+      Token::Integer(value, _) => write!(f, "{}", value),
+      // Whole reals keep their decimal point so they are not mistaken for integers.
+      Token::RealLiteral(value, _) if value.fract() == 0.0 => write!(f, "{:.1}", value),
+      Token::RealLiteral(value, _) => write!(f, "{}", value),
+      Token::String(string, _) => write_escaped(f, string, '"'),
+      Token::CharLiteral(character, _) => write_escaped(f, &character.to_string(), '\''),
+      Token::Comment(comment, _) => write!(f, "{}", comment),
+      Token::Keyword(keyword, _) => write!(f, "{}", keyword),
+      Token::Define(_) => write!(f, "define"),
+      Token::Not(_) => write!(f, "not"),
+      Token::Variable(_) => write!(f, "variable"),
+      Token::Constant(_) => write!(f, "constant"),
+      Token::Is(_) => write!(f, "is"),
+      Token::Natural(_) => write!(f, "natural"),
+      Token::Real(_) => write!(f, "real"),
+      Token::Char(_) => write!(f, "char"),
+      Token::Boolean(_) => write!(f, "boolean"),
+      Token::Execute(_) => write!(f, "execute"),
+      Token::Function(_) => write!(f, "function"),
+      Token::Return(_) => write!(f, "return"),
+      Token::Set(_) => write!(f, "set"),
+      Token::Get(_) => write!(f, "get"),
+      Token::To(_) => write!(f, "to"),
+      Token::Put(_) => write!(f, "put"),
+      Token::Loop(_) => write!(f, "loop"),
+      Token::While(_) => write!(f, "while"),
+      Token::Do(_) => write!(f, "do"),
+      Token::If(_) => write!(f, "if"),
+      Token::Then(_) => write!(f, "then"),
+      Token::Else(_) => write!(f, "else"),
+      Token::End(_) => write!(f, "end"),
+      Token::True(_) => write!(f, "true"),
+      Token::False(_) => write!(f, "false"),
+      Token::Xor(_) => write!(f, "xor"),
+      Token::Eof(_) => write!(f, "<eof>"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lex_luthor::LexLuthor;
+  use std::collections::HashMap;
+
+  #[test]
+  fn operators_display_as_their_source() {
+    let operators = vec![
+      "{", "}", "[", "]", ",", ":", ":=", ";", ".", "..", "+", "-", "*", "/", "//", "**", "%",
+      "%%", "=", "==", "!=", "<", ">", "<=", ">=", "<<", ">>", "^", "&", "&&", "|", "||", "!", "(",
+      ")",
+    ];
+
+    for operator in operators {
+      let tokens = LexLuthor::new(operator.to_owned()).lex().unwrap();
+
+      assert_eq!(operator, tokens[0].to_string());
+    }
+  }
+
+  #[test]
+  fn displays_lexemes() {
+    let test_cases = vec![
+      (
+        Token::Identifier("x".to_owned(), SourceSpan::new(1, 1, 1, 1)),
+        "x",
+      ),
+      (Token::Program(SourceSpan::new(1, 1, 1, 7)), "program"),
+      (Token::Integer(42, SourceSpan::new(1, 1, 1, 2)), "42"),
+      (Token::RealLiteral(2.5, SourceSpan::new(1, 1, 1, 3)), "2.5"),
+      (Token::RealLiteral(3.0, SourceSpan::new(1, 1, 1, 3)), "3.0"),
+      (
+        Token::String("a\"b\n".to_owned(), SourceSpan::new(1, 1, 1, 8)),
+        r#""a\"b\n""#,
+      ),
+      (
+        Token::CharLiteral('\'', SourceSpan::new(1, 1, 1, 4)),
+        r"'\''",
+      ),
+      (Token::Eof(SourceSpan::new(1, 1, 1, 1)), "<eof>"),
+    ];
+
+    for (token, expected) in test_cases {
+      assert_eq!(expected, token.to_string());
+    }
+  }
+
+  #[test]
+  fn kinds_ignore_spans_and_payloads() {
+    let tokens = LexLuthor::new("+ +\nx y".to_owned()).lex().unwrap();
+
+    assert_ne!(tokens[0], tokens[1]);
+    assert_eq!(tokens[0].kind(), tokens[1].kind());
+    assert_eq!(TokenKind::Plus, tokens[0].kind());
+    assert_eq!(tokens[2].kind(), tokens[3].kind());
+    assert_eq!(TokenKind::Identifier, tokens[2].kind());
+    assert_ne!(tokens[0].kind(), tokens[2].kind());
+  }
+
+  #[test]
+  fn dummies_have_the_given_kind_and_an_empty_span() {
+    assert_eq!(
+      Token::Plus(SourceSpan::new(0, 0, 0, 0)),
+      Token::dummy(TokenKind::Plus)
+    );
+    assert_eq!(
+      Token::Identifier(String::new(), SourceSpan::new(0, 0, 0, 0)),
+      Token::dummy(TokenKind::Identifier)
+    );
+
+    for kind in &[
+      TokenKind::Integer,
+      TokenKind::CharLiteral,
+      TokenKind::While,
+      TokenKind::Eof,
+    ] {
+      assert_eq!(*kind, Token::dummy(*kind).kind());
+    }
+  }
+
+  #[test]
+  fn tokens_know_their_span() {
+    let source_span = SourceSpan::new(1, 2, 3, 4);
+
+    assert_eq!(source_span, Token::Plus(source_span).span());
+    assert_eq!(source_span, Token::Integer(1, source_span).span());
+    assert_eq!(source_span, Token::Eof(source_span).span());
+  }
+
+  #[test]
+  fn classifies_tokens() {
+    let tokens = LexLuthor::new("program <= 42 true x (".to_owned())
+      .lex()
+      .unwrap();
+
+    let classes: Vec<_> = tokens
+      .iter()
+      .map(|token| (token.is_keyword(), token.is_operator(), token.is_literal()))
+      .collect();
+
+    assert_eq!(
+      vec![
+        (true, false, false),
+        (false, true, false),
+        (false, false, true),
+        (true, false, true),
+        (false, false, false),
+        (false, false, false),
+        (false, false, false),
+      ],
+      classes
+    );
+  }
+
+  #[test]
+  fn knows_the_reserved_words() {
+    assert!(is_reserved_word("loop"));
+    assert!(is_reserved_word("program"));
+    assert!(!is_reserved_word("loopy"));
+    assert!(!is_reserved_word("Loop"));
+  }
+
+  #[test]
+  fn operators_know_where_they_can_appear() {
+    let span = SourceSpan::new(1, 1, 1, 1);
+
+    assert!(Token::Minus(span).can_be_prefix());
+    assert!(Token::Minus(span).can_be_infix());
+    assert!(Token::Plus(span).can_be_prefix());
+    assert!(Token::Plus(span).can_be_infix());
+    assert!(!Token::StarStar(span).can_be_prefix());
+    assert!(Token::StarStar(span).can_be_infix());
+    assert!(Token::Not(span).can_be_prefix());
+    assert!(Token::Bang(span).can_be_prefix());
+    assert!(!Token::Bang(span).can_be_infix());
+    assert!(!Token::LeftParen(span).can_be_prefix());
+    assert!(!Token::Integer(1, span).can_be_infix());
+  }
+
+  #[test]
+  fn binary_operators_know_their_precedence() {
+    let span = SourceSpan::new(1, 1, 1, 1);
+
+    assert!(Token::StarStar(span).precedence() > Token::Star(span).precedence());
+    assert!(Token::Star(span).precedence() > Token::Plus(span).precedence());
+    assert_eq!(
+      Token::Plus(span).precedence(),
+      Token::Minus(span).precedence()
+    );
+    assert!(Token::Plus(span).precedence() > Token::LessThan(span).precedence());
+    assert!(Token::LessThan(span).precedence() > Token::Ampersand(span).precedence());
+    assert!(Token::Ampersand(span).precedence() > Token::Pipe(span).precedence());
+    assert_eq!(None, Token::Bang(span).precedence());
+    assert_eq!(None, Token::Identifier("x".to_owned(), span).precedence());
+  }
+
+  #[test]
+  fn token_streams_must_end_with_a_single_eof() {
+    let span = SourceSpan::new(1, 1, 1, 1);
+
+    assert!(Token::Eof(span).is_eof());
+    assert!(!Token::Plus(span).is_eof());
+
+    assert_eq!(
+      Ok(()),
+      assert_well_formed(&LexLuthor::new("x + 1".to_owned()).lex().unwrap())
+    );
+    assert_eq!(
+      Err("the tokens don't end with Eof".to_owned()),
+      assert_well_formed(&[Token::Plus(span)])
+    );
+    assert_eq!(
+      Err("Eof at index 0 is followed by 1 more tokens".to_owned()),
+      assert_well_formed(&[Token::Eof(span), Token::Plus(span)])
+    );
+    assert_eq!(
+      Err("expected exactly one Eof, found 2 at indexes [1, 2]".to_owned()),
+      assert_well_formed(&[Token::Plus(span), Token::Eof(span), Token::Eof(span)])
+    );
+  }
+
+  #[test]
+  fn pretty_prints_tokens_as_a_table() {
+    let tokens = LexLuthor::new("total = 42\n  loop".to_owned())
+      .lex()
+      .unwrap();
+
+    let table = pretty_print_tokens(&tokens);
+
+    assert_eq!(tokens.len(), table.lines().count());
+    assert!(table.contains("Identifier  total  1:1\n"));
+    assert!(table.contains("Integer     42     1:9\n"));
+    assert!(table.contains("Loop        loop   2:3\n"));
+    assert!(table.ends_with("Eof         <eof>  2:7\n"));
+  }
+
+  #[test]
+  fn keywords_know_their_lexeme() {
+    let tokens = LexLuthor::new("loop x While".to_owned())
+      .with_case_insensitive_keywords(true)
+      .lex()
+      .unwrap();
+
+    assert_eq!(Some("loop"), tokens[0].keyword_lexeme());
+    assert_eq!(SourceSpan::new(1, 1, 1, 4), tokens[0].span());
+    assert_eq!(None, tokens[1].keyword_lexeme());
+    assert_eq!(Some("while"), tokens[2].keyword_lexeme());
+    assert_eq!(
+      None,
+      Token::Plus(SourceSpan::new(1, 1, 1, 1)).keyword_lexeme()
+    );
+  }
+
+  #[test]
+  fn not_and_bang_are_both_logical_negation() {
+    let tokens = LexLuthor::new("not ! notable ~".to_owned())
+      .lex_with_recovery()
+      .0;
+
+    assert_eq!(
+      vec![true, true, false, false],
+      tokens
+        .iter()
+        .map(Token::as_logical_negation)
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(
+      Token::Identifier("notable".to_owned(), SourceSpan::new(1, 7, 1, 13)),
+      tokens[2]
+    );
+  }
+
+  #[test]
+  fn tokens_can_be_cloned() {
+    let tokens = LexLuthor::new("x + 2.5 \"s\"".to_owned()).lex().unwrap();
+
+    let cloned = tokens.clone();
+
+    assert_eq!(tokens, cloned);
+  }
+
+  #[test]
+  fn tokens_can_be_used_as_map_keys() {
+    let mut counts = HashMap::new();
+
+    for token in [
+      Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+      Token::Plus(SourceSpan::new(1, 1, 1, 1)),
+      Token::Plus(SourceSpan::new(1, 3, 1, 3)),
+      Token::RealLiteral(0.0, SourceSpan::new(1, 5, 1, 7)),
+      Token::RealLiteral(-0.0, SourceSpan::new(1, 5, 1, 7)),
+    ] {
+      *counts.entry(token).or_insert(0) += 1;
+    }
+
+    assert_eq!(
+      Some(&2),
+      counts.get(&Token::Plus(SourceSpan::new(1, 1, 1, 1)))
+    );
+    assert_eq!(
+      Some(&1),
+      counts.get(&Token::Plus(SourceSpan::new(1, 3, 1, 3)))
+    );
+    assert_eq!(
+      Some(&2),
+      counts.get(&Token::RealLiteral(0.0, SourceSpan::new(1, 5, 1, 7)))
+    );
+  }
+
+  #[test]
+  fn source_code_can_be_rebuilt_from_tokens() {
+    let source_code = "program # the whole program\n  define x: natural := 0x1F;\n  set y to x // 2 ** 3.5 <= 'c' /* block */ && !\"a\\\"b\\n\"\nend";
+
+    let kinds = |source_code: &str| -> Vec<TokenKind> {
+      LexLuthor::new(source_code.to_owned())
+        .preserving_comments(true)
+        .lex()
+        .unwrap()
+        .iter()
+        .map(Token::kind)
+        .collect()
+    };
+
+    let tokens = LexLuthor::new(source_code.to_owned())
+      .preserving_comments(true)
+      .lex()
+      .unwrap();
+    let rebuilt = to_source(&tokens);
+
+    assert_eq!(
+      "program # the whole program\ndefine x : natural := 31 ; set y to x // 2 ** 3.5 <= 'c' /* block */ && ! \"a\\\"b\\n\" end ",
+      rebuilt
+    );
+    assert_eq!(kinds(source_code), kinds(&rebuilt));
+
+    // Raw strings come back as ordinary strings, so their line breaks are escaped.
+    let tokens = LexLuthor::new("r\"a\r\nb\rc\"".to_owned()).lex().unwrap();
+    let rebuilt = to_source(&tokens);
+
+    assert_eq!("\"a\\r\\nb\\rc\" ", rebuilt);
+    assert_eq!(
+      Token::String("a\r\nb\rc".to_owned(), SourceSpan::new(1, 1, 1, 11)),
+      LexLuthor::new(rebuilt).lex().unwrap()[0]
+    );
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn tokens_round_trip_through_json() {
+    let tokens = LexLuthor::new("x + 2.5".to_owned()).lex().unwrap();
+
+    let json = serde_json::to_string(&tokens).unwrap();
+
+    assert!(json.starts_with(
+      r#"[{"Identifier":["x",{"line":1,"column":1,"end_line":1,"end_column":1}]},{"Plus":"#
+    ));
+    assert_eq!(tokens, serde_json::from_str::<Vec<Token>>(&json).unwrap());
   }
 }